@@ -0,0 +1,211 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use curver_backend::{
+    client_sink::{CurverAddress, InMemoryClientSink},
+    config::{default_worker_count, GameSettings},
+    constants::CLIENT_OUTGOING_QUEUE_CAPACITY,
+    game::{bot::BotDifficulty, player::PlayerUuid, GameState},
+    http::spawn_server,
+    message::{CurverMessageToReceive, CurverMessageToSend, ForwardedMessage},
+    metrics::metrics,
+    room_access::RoomVisibility,
+    routing::Router,
+};
+use rand::Rng;
+
+/// Runs thousands of rooms in process, each with one simulated client and one bot,
+/// and reports how many ticks, inputs and messages the server gets through.
+///
+/// `cargo run --release --example load_test -- --rooms 5000 --workers 4`
+#[derive(Parser, Debug)]
+struct Args {
+    #[clap(long, default_value_t = 2000)]
+    rooms: usize,
+
+    /// Defaults to the number of CPUs
+    #[clap(long)]
+    workers: Option<usize>,
+
+    #[clap(long, default_value_t = 10)]
+    seconds: u64,
+
+    /// Rotations every client sends per second
+    #[clap(long, default_value_t = 20)]
+    rotations_per_second: u64,
+}
+
+#[derive(Default)]
+struct Stats {
+    rooms_joined: AtomicU64,
+    inputs_sent: AtomicU64,
+    messages_received: AtomicU64,
+    overloaded: AtomicU64,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+    let worker_count = args.workers.unwrap_or_else(default_worker_count);
+
+    let _ = GameSettings {
+        countdown_seconds: 0,
+        ..GameSettings::default()
+    }
+    .install();
+
+    let app_state = spawn_server(None, None, worker_count);
+    let stats = Arc::new(Stats::default());
+
+    println!(
+        "Starting {} rooms on {} workers for {} seconds",
+        args.rooms, worker_count, args.seconds
+    );
+
+    for _ in 0..args.rooms {
+        tokio::spawn(run_client(
+            app_state.router(),
+            stats.clone(),
+            Duration::from_millis(1000 / args.rotations_per_second.max(1)),
+        ));
+    }
+
+    let started_at = Instant::now();
+    let mut last_report = Report::take(&stats);
+
+    for second in 1..=args.seconds {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let report = Report::take(&stats);
+        println!(
+            "{:>3}s  joined {:>6}  ticks/s {:>8}  inputs/s {:>8}  messages/s {:>9}  games {:>6}  overloaded {:>6}",
+            second,
+            report.rooms_joined,
+            report.ticks - last_report.ticks,
+            report.inputs_sent - last_report.inputs_sent,
+            report.messages_received - last_report.messages_received,
+            report.games_finished,
+            report.overloaded,
+        );
+        last_report = report;
+    }
+
+    let elapsed = started_at.elapsed().as_secs_f64();
+    let tick_seconds = metrics().tick_duration.get_sample_sum();
+
+    println!(
+        "Average: {:.0} ticks/s, {:.0} inputs/s, {:.0} messages/s, {:.1} µs per tick",
+        last_report.ticks as f64 / elapsed,
+        last_report.inputs_sent as f64 / elapsed,
+        last_report.messages_received as f64 / elapsed,
+        tick_seconds * 1_000_000.0 / last_report.ticks.max(1) as f64,
+    );
+}
+
+struct Report {
+    rooms_joined: u64,
+    ticks: u64,
+    inputs_sent: u64,
+    messages_received: u64,
+    games_finished: u64,
+    overloaded: u64,
+}
+
+impl Report {
+    fn take(stats: &Stats) -> Report {
+        Report {
+            rooms_joined: stats.rooms_joined.load(Ordering::Relaxed),
+            ticks: metrics().tick_duration.get_sample_count(),
+            inputs_sent: stats.inputs_sent.load(Ordering::Relaxed),
+            messages_received: stats.messages_received.load(Ordering::Relaxed),
+            games_finished: metrics().games_finished.get(),
+            overloaded: stats.overloaded.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Creates a room, adds a bot and plays one game after another in that room.
+async fn run_client(router: Router, stats: Arc<Stats>, rotation_interval: Duration) {
+    let user_id = PlayerUuid::new();
+    let (sink, mut receiver) = InMemoryClientSink::new(CLIENT_OUTGOING_QUEUE_CAPACITY);
+    let address: CurverAddress = Arc::new(sink);
+
+    let send = |message| {
+        router.forward(ForwardedMessage {
+            message,
+            user_id,
+            address: address.clone(),
+        })
+    };
+    let create_room = || CurverMessageToReceive::CreateRoom {
+        visibility: RoomVisibility::Private,
+        password: None,
+        max_players: None,
+    };
+
+    send(CurverMessageToReceive::Connect);
+    send(create_room());
+
+    let mut rotation_interval = tokio::time::interval(rotation_interval);
+    let mut in_room = false;
+
+    loop {
+        tokio::select! {
+            Some(outgoing) = receiver.recv() => {
+                for message in outgoing.messages() {
+                    stats.messages_received.fetch_add(1, Ordering::Relaxed);
+
+                    match message {
+                        CurverMessageToSend::JoinedRoom { .. } => {
+                            in_room = true;
+                            stats.rooms_joined.fetch_add(1, Ordering::Relaxed);
+
+                            send(CurverMessageToReceive::AddBot {
+                                difficulty: BotDifficulty::Medium,
+                            });
+                        }
+
+                        CurverMessageToSend::Update { players, game_state } => {
+                            let is_ready = players
+                                .iter()
+                                .any(|player| player.id == user_id && player.is_ready);
+
+                            if *game_state == GameState::Waiting && players.len() > 1 && !is_ready {
+                                send(CurverMessageToReceive::IsReady { is_ready: true });
+                            }
+                        }
+
+                        // The server handler's queue was full, try again a little later
+                        CurverMessageToSend::ServerOverloaded { message_type } => {
+                            stats.overloaded.fetch_add(1, Ordering::Relaxed);
+
+                            if message_type == "createRoom" {
+                                let backoff = rand::thread_rng().gen_range(10..200);
+                                tokio::time::sleep(Duration::from_millis(backoff)).await;
+                                send(create_room());
+                            }
+                        }
+
+                        _ => {}
+                    }
+                }
+            }
+
+            _ = rotation_interval.tick(), if in_room => {
+                let angle = rand::thread_rng().gen_range(0.0..std::f32::consts::TAU);
+
+                send(CurverMessageToReceive::Rotate {
+                    angle_unit_vector_x: angle.cos(),
+                    angle_unit_vector_y: angle.sin(),
+                });
+                stats.inputs_sent.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
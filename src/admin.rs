@@ -0,0 +1,332 @@
+use std::{collections::HashMap, time::Duration};
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::{
+    constants::DRAIN_TIMEOUT_SECONDS,
+    curver_error::AdminError,
+    game::{
+        player::{Player, PlayerUuid},
+        Paths,
+    },
+    http::AppState,
+    room::RoomUuid,
+    room_access::RoomVisibility,
+    room_handle::{RoomHandle, RoomSummary},
+};
+
+/// Operator actions that change server state, so they are carried out by the `ServerHandler`.
+#[derive(Debug)]
+pub enum AdminCommand {
+    ListPlayers {
+        respond: oneshot::Sender<Vec<PlayerInfo>>,
+    },
+    KickPlayer {
+        user_id: PlayerUuid,
+        respond: oneshot::Sender<Result<(), AdminError>>,
+    },
+    CloseRoom {
+        room_id: RoomUuid,
+        reason: String,
+        respond: oneshot::Sender<Result<(), AdminError>>,
+    },
+    /// Responds once every room is gone or the timeout has passed.
+    Drain {
+        timeout: Duration,
+        respond: oneshot::Sender<()>,
+    },
+    /// Responds with the number of connections that got the notice.
+    BroadcastNotice {
+        text: String,
+        respond: oneshot::Sender<usize>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerInfo {
+    #[serde(rename = "userId")]
+    pub user_id: PlayerUuid,
+    #[serde(rename = "roomId")]
+    pub room_id: Option<RoomUuid>,
+    #[serde(rename = "inMatchmaking")]
+    pub in_matchmaking: bool,
+    #[serde(rename = "subscribedToLobby")]
+    pub subscribed_to_lobby: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdminRoomSummary {
+    #[serde(flatten)]
+    pub summary: RoomSummary,
+    #[serde(rename = "hostId")]
+    pub host_id: Option<PlayerUuid>,
+    pub visibility: RoomVisibility,
+    pub members: Vec<PlayerUuid>,
+    pub bots: Vec<PlayerUuid>,
+}
+
+impl AdminRoomSummary {
+    fn new(room_handle: &RoomHandle) -> Self {
+        Self {
+            summary: room_handle.summary(),
+            host_id: room_handle.host,
+            visibility: room_handle.access.visibility,
+            members: room_handle.members.keys().cloned().collect(),
+            bots: room_handle.bots.iter().cloned().collect(),
+        }
+    }
+}
+
+/// Paths are only as recent as the last sync sent to the clients.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdminRoomDetails {
+    #[serde(flatten)]
+    pub room: AdminRoomSummary,
+    pub players: Vec<Player>,
+    #[serde(rename = "scoreBoard")]
+    pub score_board: HashMap<PlayerUuid, u32>,
+    pub paths: Paths,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloseRoomRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NoticeRequest {
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DrainRequest {
+    #[serde(default, rename = "timeoutSeconds")]
+    pub timeout_seconds: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct NoticeResponse {
+    recipients: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    code: AdminError,
+    reason: String,
+}
+
+/// Only registered when an admin token is configured.
+pub fn configure_admin_services(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin")
+            .route("/rooms", web::get().to(list_rooms))
+            .route("/rooms/{room_id}", web::get().to(inspect_room))
+            .route("/rooms/{room_id}/close", web::post().to(close_room))
+            .route("/players", web::get().to(list_players))
+            .route("/players/{user_id}/kick", web::post().to(kick_player))
+            .route("/notice", web::post().to(broadcast_notice))
+            .route("/drain", web::post().to(start_drain)),
+    );
+}
+
+/// Expects `Authorization: Bearer <token>`.
+fn authorize(req: &HttpRequest, app_state: &AppState) -> Result<(), HttpResponse> {
+    let Some(admin_token) = &app_state.admin_token else {
+        return Err(HttpResponse::NotFound().finish());
+    };
+
+    let provided = req
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+
+    if !constant_time_eq(provided.as_bytes(), admin_token.as_bytes()) {
+        return Err(HttpResponse::Unauthorized().finish());
+    }
+
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+async fn send_command<T>(
+    app_state: &AppState,
+    command: impl FnOnce(oneshot::Sender<T>) -> AdminCommand,
+) -> Result<T, HttpResponse> {
+    let (respond, response) = oneshot::channel();
+
+    app_state
+        .admin_command_transmitter
+        .send(command(respond))
+        .await
+        .map_err(|_| HttpResponse::ServiceUnavailable().finish())?;
+
+    response
+        .await
+        .map_err(|_| HttpResponse::ServiceUnavailable().finish())
+}
+
+fn error_response(error: AdminError) -> HttpResponse {
+    HttpResponse::NotFound().json(ErrorResponse {
+        reason: error.to_string(),
+        code: error,
+    })
+}
+
+async fn list_rooms(req: HttpRequest, app_state: web::Data<AppState>) -> HttpResponse {
+    if let Err(response) = authorize(&req, &app_state) {
+        return response;
+    }
+
+    let rooms: Vec<AdminRoomSummary> = app_state
+        .rooms
+        .read()
+        .values()
+        .map(AdminRoomSummary::new)
+        .collect();
+
+    HttpResponse::Ok().json(rooms)
+}
+
+async fn inspect_room(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    room_id: web::Path<RoomUuid>,
+) -> HttpResponse {
+    if let Err(response) = authorize(&req, &app_state) {
+        return response;
+    }
+
+    let rooms_lock = app_state.rooms.read();
+
+    let Some(room_handle) = rooms_lock.get(&room_id) else {
+        return error_response(AdminError::RoomNotFound);
+    };
+
+    let snapshot = room_handle.snapshot.borrow();
+    let details = AdminRoomDetails {
+        room: AdminRoomSummary::new(room_handle),
+        players: snapshot.players.values().cloned().collect(),
+        score_board: snapshot.score_board.clone(),
+        paths: snapshot.paths.clone(),
+    };
+
+    HttpResponse::Ok().json(details)
+}
+
+async fn close_room(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    room_id: web::Path<RoomUuid>,
+    body: Option<web::Json<CloseRoomRequest>>,
+) -> HttpResponse {
+    if let Err(response) = authorize(&req, &app_state) {
+        return response;
+    }
+
+    let reason = body
+        .and_then(|body| body.into_inner().reason)
+        .unwrap_or_else(|| "Room was closed by an operator".to_string());
+    let room_id = room_id.into_inner();
+
+    match send_command(&app_state, |respond| AdminCommand::CloseRoom {
+        room_id,
+        reason,
+        respond,
+    })
+    .await
+    {
+        Ok(Ok(())) => HttpResponse::NoContent().finish(),
+        Ok(Err(error)) => error_response(error),
+        Err(response) => response,
+    }
+}
+
+async fn list_players(req: HttpRequest, app_state: web::Data<AppState>) -> HttpResponse {
+    if let Err(response) = authorize(&req, &app_state) {
+        return response;
+    }
+
+    match send_command(&app_state, |respond| AdminCommand::ListPlayers { respond }).await {
+        Ok(players) => HttpResponse::Ok().json(players),
+        Err(response) => response,
+    }
+}
+
+async fn kick_player(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    user_id: web::Path<PlayerUuid>,
+) -> HttpResponse {
+    if let Err(response) = authorize(&req, &app_state) {
+        return response;
+    }
+
+    let user_id = user_id.into_inner();
+
+    match send_command(&app_state, |respond| AdminCommand::KickPlayer {
+        user_id,
+        respond,
+    })
+    .await
+    {
+        Ok(Ok(())) => HttpResponse::NoContent().finish(),
+        Ok(Err(error)) => error_response(error),
+        Err(response) => response,
+    }
+}
+
+async fn broadcast_notice(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    body: web::Json<NoticeRequest>,
+) -> HttpResponse {
+    if let Err(response) = authorize(&req, &app_state) {
+        return response;
+    }
+
+    let text = body.into_inner().text;
+
+    match send_command(&app_state, |respond| AdminCommand::BroadcastNotice {
+        text,
+        respond,
+    })
+    .await
+    {
+        Ok(recipients) => HttpResponse::Ok().json(NoticeResponse { recipients }),
+        Err(response) => response,
+    }
+}
+
+/// Starts draining without waiting for it to finish. The process keeps running afterwards.
+async fn start_drain(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    body: Option<web::Json<DrainRequest>>,
+) -> HttpResponse {
+    if let Err(response) = authorize(&req, &app_state) {
+        return response;
+    }
+
+    let timeout_seconds = body
+        .and_then(|body| body.into_inner().timeout_seconds)
+        .unwrap_or(DRAIN_TIMEOUT_SECONDS);
+
+    let (respond, _) = oneshot::channel();
+    let command = AdminCommand::Drain {
+        timeout: Duration::from_secs(timeout_seconds),
+        respond,
+    };
+
+    match app_state.admin_command_transmitter.send(command).await {
+        Ok(()) => HttpResponse::Accepted().finish(),
+        Err(_) => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
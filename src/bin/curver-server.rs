@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use actix_web::{App, HttpServer};
+use clap::Parser;
+use curver_backend::{
+    config::Config,
+    http::{configure_services, drain, spawn_server},
+    telemetry::init_tracing,
+};
+
+/// Runs the server on plain actix-web, without the shuttle runtime.
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let config = Config::parse();
+
+    if let Err(error) = init_tracing(&config.log_level, config.log_format) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+
+    let game_settings = match config.game_settings() {
+        Ok(game_settings) => game_settings,
+        Err(error) => {
+            tracing::error!(%error, "Invalid game settings");
+            std::process::exit(1);
+        }
+    };
+    tracing::info!(?game_settings, "Game settings loaded");
+
+    if game_settings.install().is_err() {
+        tracing::error!("Game settings were used before they were installed");
+        std::process::exit(1);
+    }
+
+    let app_state = spawn_server(
+        config.tcp_address.clone(),
+        config.admin_token.clone(),
+        config.worker_count(),
+    );
+    let server_app_state = app_state.clone();
+
+    tracing::info!(address = %config.address, port = config.port, "Listening");
+
+    // Signals are handled here, so the games can be drained before the workers stop
+    let server = HttpServer::new(move || {
+        App::new().configure(|cfg| configure_services(cfg, server_app_state.clone()))
+    })
+    .bind((config.address.as_str(), config.port))?
+    .disable_signals()
+    .run();
+    let server_handle = server.handle();
+    let mut server = actix_web::rt::spawn(server);
+
+    tokio::select! {
+        result = &mut server => return result.expect("server task does not panic"),
+        _ = shutdown_signal() => {}
+    }
+
+    tracing::info!(
+        timeout_seconds = config.drain_timeout_seconds,
+        "Shutdown requested, draining rooms"
+    );
+
+    tokio::select! {
+        drained = drain(&app_state, Duration::from_secs(config.drain_timeout_seconds)) => {
+            if drained {
+                tracing::info!("All rooms are closed");
+            }
+        }
+        _ = shutdown_signal() => {
+            tracing::warn!("Shutdown requested again, stopping without draining");
+        }
+    }
+
+    server_handle.stop(true).await;
+
+    Ok(())
+}
+
+/// Resolves on Ctrl+C, or on SIGTERM as sent by container runtimes.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("SIGTERM handler installs");
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
@@ -0,0 +1,98 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    constants::{CHAT_RATE_LIMIT_MESSAGES, CHAT_RATE_LIMIT_WINDOW_SECONDS},
+    game::player::PlayerUuid,
+};
+
+/// Cleans up chat messages before they are broadcast.
+pub trait WordFilter: Send + Sync {
+    fn filter(&self, text: &str) -> String;
+}
+
+/// Masks every word listed in a local file, one word per line, ignoring case.
+pub struct FileWordFilter {
+    words: Vec<String>,
+}
+
+impl FileWordFilter {
+    /// A missing file results in a filter that lets everything through.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let words = fs::read_to_string(path)
+            .map(|content| {
+                content
+                    .lines()
+                    .map(|line| line.trim().to_lowercase())
+                    .filter(|word| !word.is_empty() && !word.starts_with('#'))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { words }
+    }
+}
+
+impl WordFilter for FileWordFilter {
+    fn filter(&self, text: &str) -> String {
+        text.split(' ')
+            .map(|word| {
+                let normalized: String = word
+                    .chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .collect::<String>()
+                    .to_lowercase();
+
+                if self.words.contains(&normalized) {
+                    "*".repeat(word.chars().count())
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+/// Allows each player a fixed number of messages per sliding window.
+#[derive(Default)]
+pub struct ChatRateLimiter {
+    sent_at: HashMap<PlayerUuid, VecDeque<Instant>>,
+}
+
+impl ChatRateLimiter {
+    pub fn new() -> Self {
+        Self {
+            sent_at: HashMap::new(),
+        }
+    }
+
+    /// Records the message if it is allowed.
+    pub fn try_acquire(&mut self, user_id: PlayerUuid) -> bool {
+        let now = Instant::now();
+        let window = Duration::from_secs(CHAT_RATE_LIMIT_WINDOW_SECONDS);
+        let sent_at = self.sent_at.entry(user_id).or_default();
+
+        while sent_at
+            .front()
+            .is_some_and(|sent_at| now.duration_since(*sent_at) > window)
+        {
+            sent_at.pop_front();
+        }
+
+        if sent_at.len() >= CHAT_RATE_LIMIT_MESSAGES {
+            return false;
+        }
+
+        sent_at.push_back(now);
+        true
+    }
+
+    pub fn forget(&mut self, user_id: PlayerUuid) {
+        self.sent_at.remove(&user_id);
+    }
+}
@@ -0,0 +1,94 @@
+use std::{slice, sync::Arc};
+
+use tokio::sync::{
+    mpsc::{self, error::TrySendError, Receiver, Sender},
+    Notify,
+};
+
+use crate::message::{CurverMessageToSend, Frame};
+
+/// Anything the server can push messages to. Sending never blocks and silently drops
+/// the message if the client is gone.
+pub trait ClientSink: Send + Sync {
+    fn do_send(&self, message: CurverMessageToSend);
+
+    /// Sends messages that were already serialized, usually for many clients at once.
+    fn send_frame(&self, frame: Frame);
+}
+
+/// Clients of every transport can share a room, since rooms only see this.
+pub type CurverAddress = Arc<dyn ClientSink>;
+
+/// Serializes the messages once and sends the same frame to every client.
+pub fn broadcast<'a>(
+    clients: impl IntoIterator<Item = &'a CurverAddress>,
+    messages: Vec<CurverMessageToSend>,
+) {
+    let mut clients = clients.into_iter().peekable();
+
+    if messages.is_empty() || clients.peek().is_none() {
+        return;
+    }
+
+    let frame = Frame::new(messages);
+
+    for client in clients {
+        client.send_frame(frame.clone());
+    }
+}
+
+/// What an in memory client receives.
+pub enum Outgoing {
+    Message(CurverMessageToSend),
+    Frame(Frame),
+}
+
+impl Outgoing {
+    pub fn messages(&self) -> &[CurverMessageToSend] {
+        match self {
+            Outgoing::Message(message) => slice::from_ref(message),
+            Outgoing::Frame(frame) => frame.messages(),
+        }
+    }
+}
+
+/// Collects messages in a channel, for tests and anything else running in the same process.
+/// Once `capacity` messages wait for the client, it is lagging and further messages are dropped.
+pub struct InMemoryClientSink {
+    transmitter: Sender<Outgoing>,
+    lagging: Arc<Notify>,
+}
+
+impl InMemoryClientSink {
+    pub fn new(capacity: usize) -> (InMemoryClientSink, Receiver<Outgoing>) {
+        let (transmitter, receiver) = mpsc::channel(capacity);
+
+        let sink = InMemoryClientSink {
+            transmitter,
+            lagging: Arc::new(Notify::new()),
+        };
+
+        (sink, receiver)
+    }
+
+    /// Notified once the client falls behind, so its connection can be closed.
+    pub fn lagging(&self) -> Arc<Notify> {
+        self.lagging.clone()
+    }
+
+    fn send(&self, outgoing: Outgoing) {
+        if let Err(TrySendError::Full(_)) = self.transmitter.try_send(outgoing) {
+            self.lagging.notify_one();
+        }
+    }
+}
+
+impl ClientSink for InMemoryClientSink {
+    fn do_send(&self, message: CurverMessageToSend) {
+        self.send(Outgoing::Message(message));
+    }
+
+    fn send_frame(&self, frame: Frame) {
+        self.send(Outgoing::Frame(frame));
+    }
+}
@@ -1,13 +1,284 @@
-use clap::Parser;
+use std::{fs, path::PathBuf, sync::OnceLock};
 
+use clap::{Args, Parser};
+use serde::Deserialize;
+
+use crate::{
+    constants::{
+        DEFAULT_MAX_PLAYERS_PER_ROOM, DELTA_POS_PER_SECOND, DRAIN_TIMEOUT_SECONDS,
+        GAME_START_COUNTDOWN_SECONDS, MAP_HEIGHT, MAP_WIDTH, MATCHMAKING_MAX_WAIT_SECONDS,
+        MATCHMAKING_ROOM_SIZE, MAX_PLAYERS_PER_ROOM_LIMIT, MIN_PLAYERS_TO_START,
+        PLAYER_GAINED_POINTS_PER_TICK, RECONNECT_GRACE_SECONDS, TICK_COUNT_TO_SYNC, TICK_RATE,
+    },
+    game::DisconnectPolicy,
+    telemetry::LogFormat,
+};
+
+/// Every option can also be set through its environment variable.
+/// Game settings are read from the TOML file first, then overridden by the environment
+/// and finally by the command line.
 #[derive(Parser, Debug)]
 #[command(author, version)]
 pub struct Config {
     /// Server address
-    #[clap(short, long, default_value = "0.0.0.0")]
+    #[clap(short, long, env = "CURVER_ADDRESS", default_value = "0.0.0.0")]
     pub address: String,
 
     /// Server port
-    #[clap(short, long, default_value = "8080")]
+    #[clap(short, long, env = "CURVER_PORT", default_value = "8080")]
     pub port: u16,
+
+    /// Address to accept raw TCP clients on, disabled if not set
+    #[clap(long, env = "CURVER_TCP_ADDRESS")]
+    pub tcp_address: Option<String>,
+
+    /// Bearer token for the admin API under `/admin`, disabled if not set
+    #[clap(long, env = "CURVER_ADMIN_TOKEN", hide_env_values = true)]
+    pub admin_token: Option<String>,
+
+    /// TOML file with game settings
+    #[clap(short, long, env = "CURVER_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Log filter such as `info` or `curver_backend=debug`, `RUST_LOG` takes precedence
+    #[clap(long, env = "CURVER_LOG_LEVEL", default_value = "info")]
+    pub log_level: String,
+
+    #[clap(long, env = "CURVER_LOG_FORMAT", value_enum, default_value_t = LogFormat::Pretty)]
+    pub log_format: LogFormat,
+
+    /// Worker threads rooms are spread over, defaults to the number of CPUs
+    #[clap(long, env = "CURVER_WORKERS")]
+    pub workers: Option<usize>,
+
+    /// Seconds running games may take to finish on shutdown before their rooms are closed
+    #[clap(long, env = "CURVER_DRAIN_TIMEOUT_SECONDS", default_value_t = DRAIN_TIMEOUT_SECONDS)]
+    pub drain_timeout_seconds: u64,
+
+    #[command(flatten)]
+    pub game: GameSettingsOverrides,
+}
+
+impl Config {
+    pub fn worker_count(&self) -> usize {
+        self.workers.unwrap_or_else(default_worker_count)
+    }
+
+    pub fn game_settings(&self) -> Result<GameSettings, String> {
+        let settings = match &self.config {
+            Some(path) => {
+                let content = fs::read_to_string(path)
+                    .map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+
+                toml::from_str(&content)
+                    .map_err(|error| format!("Could not parse {}: {}", path.display(), error))?
+            }
+            None => GameSettings::default(),
+        };
+
+        let settings = self.game.apply(settings);
+        settings.validate()?;
+
+        Ok(settings)
+    }
+}
+
+pub fn default_worker_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|parallelism| parallelism.get())
+        .unwrap_or(1)
+}
+
+#[derive(Args, Debug, Default)]
+pub struct GameSettingsOverrides {
+    /// Game ticks per second
+    #[clap(long, env = "CURVER_TICK_RATE")]
+    pub tick_rate: Option<f32>,
+
+    /// Ticks between full path syncs
+    #[clap(long, env = "CURVER_TICK_COUNT_TO_SYNC")]
+    pub tick_count_to_sync: Option<u32>,
+
+    /// Distance a player moves per second
+    #[clap(long, env = "CURVER_PLAYER_SPEED")]
+    pub player_speed: Option<f32>,
+
+    #[clap(long, env = "CURVER_POINTS_PER_TICK")]
+    pub points_per_tick: Option<u32>,
+
+    #[clap(long, env = "CURVER_COUNTDOWN_SECONDS")]
+    pub countdown_seconds: Option<u64>,
+
+    #[clap(long, env = "CURVER_MAP_WIDTH")]
+    pub map_width: Option<f32>,
+
+    #[clap(long, env = "CURVER_MAP_HEIGHT")]
+    pub map_height: Option<f32>,
+
+    #[clap(long, env = "CURVER_MIN_PLAYERS_TO_START")]
+    pub min_players_to_start: Option<usize>,
+
+    #[clap(long, env = "CURVER_DEFAULT_MAX_PLAYERS")]
+    pub default_max_players: Option<usize>,
+
+    #[clap(long, env = "CURVER_MAX_PLAYERS_LIMIT")]
+    pub max_players_limit: Option<usize>,
+
+    /// Players quick play waits for before it opens a room
+    #[clap(long, env = "CURVER_MATCHMAKING_ROOM_SIZE")]
+    pub matchmaking_room_size: Option<usize>,
+
+    /// Seconds after which quick play opens a room with fewer players
+    #[clap(long, env = "CURVER_MATCHMAKING_MAX_WAIT_SECONDS")]
+    pub matchmaking_max_wait_seconds: Option<u64>,
+
+    /// What happens to a player whose client leaves a running game
+    #[clap(long, env = "CURVER_DISCONNECT_POLICY", value_enum)]
+    pub disconnect_policy: Option<DisconnectPolicy>,
+
+    /// Seconds a player who left a running game has to reconnect
+    #[clap(long, env = "CURVER_RECONNECT_GRACE_SECONDS")]
+    pub reconnect_grace_seconds: Option<u64>,
+}
+
+impl GameSettingsOverrides {
+    fn apply(&self, settings: GameSettings) -> GameSettings {
+        GameSettings {
+            tick_rate: self.tick_rate.unwrap_or(settings.tick_rate),
+            tick_count_to_sync: self
+                .tick_count_to_sync
+                .unwrap_or(settings.tick_count_to_sync),
+            player_speed: self.player_speed.unwrap_or(settings.player_speed),
+            points_per_tick: self.points_per_tick.unwrap_or(settings.points_per_tick),
+            countdown_seconds: self.countdown_seconds.unwrap_or(settings.countdown_seconds),
+            map_width: self.map_width.unwrap_or(settings.map_width),
+            map_height: self.map_height.unwrap_or(settings.map_height),
+            min_players_to_start: self
+                .min_players_to_start
+                .unwrap_or(settings.min_players_to_start),
+            default_max_players: self
+                .default_max_players
+                .unwrap_or(settings.default_max_players),
+            max_players_limit: self.max_players_limit.unwrap_or(settings.max_players_limit),
+            matchmaking_room_size: self
+                .matchmaking_room_size
+                .unwrap_or(settings.matchmaking_room_size),
+            matchmaking_max_wait_seconds: self
+                .matchmaking_max_wait_seconds
+                .unwrap_or(settings.matchmaking_max_wait_seconds),
+            disconnect_policy: self.disconnect_policy.unwrap_or(settings.disconnect_policy),
+            reconnect_grace_seconds: self
+                .reconnect_grace_seconds
+                .unwrap_or(settings.reconnect_grace_seconds),
+        }
+    }
+}
+
+/// Tunables of the game. Defaults to the values in `constants`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct GameSettings {
+    pub tick_rate: f32,
+    pub tick_count_to_sync: u32,
+    pub player_speed: f32,
+    pub points_per_tick: u32,
+    pub countdown_seconds: u64,
+    pub map_width: f32,
+    pub map_height: f32,
+    pub min_players_to_start: usize,
+    pub default_max_players: usize,
+    pub max_players_limit: usize,
+    pub matchmaking_room_size: usize,
+    pub matchmaking_max_wait_seconds: u64,
+    pub disconnect_policy: DisconnectPolicy,
+    pub reconnect_grace_seconds: u64,
+}
+
+static GAME_SETTINGS: OnceLock<GameSettings> = OnceLock::new();
+
+/// Settings every room and game of this process runs with.
+pub fn game_settings() -> &'static GameSettings {
+    GAME_SETTINGS.get_or_init(GameSettings::default)
+}
+
+impl GameSettings {
+    /// Has to happen before the first room is created, later calls are ignored.
+    pub fn install(self) -> Result<(), GameSettings> {
+        GAME_SETTINGS.set(self)
+    }
+
+    pub fn ms_per_tick(&self) -> f32 {
+        1000.0 / self.tick_rate
+    }
+
+    pub fn delta_pos_per_tick(&self) -> f32 {
+        self.player_speed / self.tick_rate
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !is_positive(self.tick_rate) {
+            return Err("tick_rate must be positive".to_string());
+        }
+
+        if self.tick_count_to_sync == 0 {
+            return Err("tick_count_to_sync must be positive".to_string());
+        }
+
+        if !is_positive(self.player_speed) {
+            return Err("player_speed must be positive".to_string());
+        }
+
+        if !is_positive(self.map_width) || !is_positive(self.map_height) {
+            return Err("map_width and map_height must be positive".to_string());
+        }
+
+        if self.min_players_to_start < 2 {
+            return Err("min_players_to_start must be at least 2".to_string());
+        }
+
+        if !(self.min_players_to_start..=self.max_players_limit).contains(&self.default_max_players)
+        {
+            return Err(
+                "default_max_players must be between min_players_to_start and max_players_limit"
+                    .to_string(),
+            );
+        }
+
+        if !(self.min_players_to_start..=self.max_players_limit)
+            .contains(&self.matchmaking_room_size)
+        {
+            return Err(
+                "matchmaking_room_size must be between min_players_to_start and max_players_limit"
+                    .to_string(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Rules out NaN and infinity as well.
+fn is_positive(value: f32) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            tick_rate: TICK_RATE,
+            tick_count_to_sync: TICK_COUNT_TO_SYNC,
+            player_speed: DELTA_POS_PER_SECOND,
+            points_per_tick: PLAYER_GAINED_POINTS_PER_TICK,
+            countdown_seconds: GAME_START_COUNTDOWN_SECONDS,
+            map_width: MAP_WIDTH,
+            map_height: MAP_HEIGHT,
+            min_players_to_start: MIN_PLAYERS_TO_START,
+            default_max_players: DEFAULT_MAX_PLAYERS_PER_ROOM,
+            max_players_limit: MAX_PLAYERS_PER_ROOM_LIMIT,
+            matchmaking_room_size: MATCHMAKING_ROOM_SIZE,
+            matchmaking_max_wait_seconds: MATCHMAKING_MAX_WAIT_SECONDS,
+            disconnect_policy: DisconnectPolicy::default(),
+            reconnect_grace_seconds: RECONNECT_GRACE_SECONDS,
+        }
+    }
 }
@@ -1,12 +1,68 @@
-const TICK_RATE: f32 = 20.0;
+//! Default game settings can be overridden at startup, see `config::GameSettings`.
+
+pub const TICK_RATE: f32 = 20.0;
 pub const TICK_COUNT_TO_SYNC: u32 = 20;
+/// Ticks a late game loop may run back to back to catch up, any further missed ticks are skipped.
+pub const MAX_CATCH_UP_TICKS: u32 = 3;
 
-const DELTA_POS_PER_SECOND: f32 = 10.0;
+pub const DELTA_POS_PER_SECOND: f32 = 10.0;
 pub const GAME_START_COUNTDOWN_SECONDS: u64 = 3;
 
-pub const MS_PER_TICK: f32 = 1000.0 / TICK_RATE;
-pub const DELTA_POS_PER_TICK: f32 = DELTA_POS_PER_SECOND / TICK_RATE;
 pub const PLAYER_GAINED_POINTS_PER_TICK: u32 = 1;
 
 pub const MAP_WIDTH: f32 = 150.0;
 pub const MAP_HEIGHT: f32 = 100.0;
+
+/// Room codes leave out I, L and O so they can't be confused with 1 and 0.
+pub const ROOM_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ";
+pub const ROOM_CODE_LENGTH: usize = 6;
+
+pub const DEFAULT_MAX_PLAYERS_PER_ROOM: usize = 8;
+pub const MAX_PLAYERS_PER_ROOM_LIMIT: usize = 16;
+pub const MIN_PLAYERS_TO_START: usize = 2;
+
+pub const MATCHMAKING_ROOM_SIZE: usize = 4;
+pub const MATCHMAKING_MAX_WAIT_SECONDS: u64 = 15;
+pub const MATCHMAKING_INTERVAL_MS: u64 = 1000;
+
+pub const INITIAL_RATING: f64 = 1500.0;
+pub const RATING_K_FACTOR: f64 = 32.0;
+pub const RATINGS_FILE_PATH: &str = "ratings.json";
+
+pub const LOBBY_UPDATE_INTERVAL_MS: u64 = 500;
+
+/// Free queue slots kept for lifecycle messages. Other messages are rejected once a queue is
+/// this full.
+pub const CONTROL_MESSAGE_RESERVE: usize = 20;
+/// Lifecycle messages of one player that may wait for a full queue. Any further ones are
+/// rejected, except for the disconnect.
+pub const OVERFLOW_CAPACITY_PER_PLAYER: usize = 16;
+
+/// Longest line a TCP client may send, longer ones disconnect the client.
+pub const TCP_MAX_LINE_LENGTH: usize = 16 * 1024;
+/// Frames that may wait for a client in the same process. A client this far behind is lagging.
+pub const CLIENT_OUTGOING_QUEUE_CAPACITY: usize = 256;
+
+pub const DRAIN_CHECK_INTERVAL_MS: u64 = 500;
+pub const DRAIN_TIMEOUT_SECONDS: u64 = 120;
+/// How long past the drain timeout rooms may take to close before the server stops anyway.
+pub const DRAIN_CLOSE_MARGIN_SECONDS: u64 = 5;
+/// Hint for clients on how long to wait before reconnecting to a restarting server.
+pub const RECONNECT_AFTER_SECONDS: u64 = 10;
+
+/// How long a player whose client left a running game can reconnect and take over again.
+pub const RECONNECT_GRACE_SECONDS: u64 = 10;
+
+pub const CHAT_MAX_LENGTH: usize = 200;
+pub const CHAT_RATE_LIMIT_MESSAGES: usize = 5;
+pub const CHAT_RATE_LIMIT_WINDOW_SECONDS: u64 = 10;
+pub const WORD_FILTER_FILE_PATH: &str = "word_filter.txt";
+
+pub const NICKNAME_MAX_LENGTH: usize = 16;
+/// Distinct trail colors, handed out in order to players of a room.
+pub const PLAYER_COLORS: &[&str] = &[
+    "#e6194b", "#3cb44b", "#ffe119", "#4363d8", "#f58231", "#911eb4", "#46f0f0", "#f032e6",
+    "#bcf60c", "#fabebe", "#008080", "#e6beff", "#9a6324", "#fffac8", "#800000", "#aaffc3",
+];
+
+pub const BOT_MAX_TURN_RADIANS_PER_TICK: f32 = 0.15;
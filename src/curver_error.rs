@@ -1,5 +1,6 @@
 use core::fmt;
 
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
@@ -14,3 +15,114 @@ impl fmt::Display for ServerError {
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum JoinRoomError {
+    #[serde(rename = "roomDoesNotExist")]
+    RoomDoesNotExist,
+    #[serde(rename = "passwordRequired")]
+    PasswordRequired,
+    #[serde(rename = "wrongPassword")]
+    WrongPassword,
+    #[serde(rename = "inviteRequired")]
+    InviteRequired,
+    #[serde(rename = "invalidInviteToken")]
+    InvalidInviteToken,
+    #[serde(rename = "roomFull")]
+    RoomFull,
+    #[serde(rename = "gameInProgress")]
+    GameInProgress,
+    #[serde(rename = "alreadyInRoom")]
+    AlreadyInRoom,
+    #[serde(rename = "roomLocked")]
+    RoomLocked,
+    #[serde(rename = "serverShuttingDown")]
+    ServerShuttingDown,
+    #[serde(rename = "invalidReconnectToken")]
+    InvalidReconnectToken,
+    #[serde(rename = "seatNotHeld")]
+    SeatNotHeld,
+}
+
+impl fmt::Display for JoinRoomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JoinRoomError::RoomDoesNotExist => write!(f, "Room does not exist"),
+            JoinRoomError::PasswordRequired => write!(f, "Room requires a password"),
+            JoinRoomError::WrongPassword => write!(f, "Wrong password"),
+            JoinRoomError::InviteRequired => write!(f, "Room is private and requires an invite"),
+            JoinRoomError::InvalidInviteToken => write!(f, "Invite token is invalid or used"),
+            JoinRoomError::RoomFull => write!(f, "Room is full"),
+            JoinRoomError::GameInProgress => {
+                write!(f, "Game is in progress, wait for the next round")
+            }
+            JoinRoomError::AlreadyInRoom => write!(f, "Already in this room"),
+            JoinRoomError::RoomLocked => write!(f, "Room is locked by its host"),
+            JoinRoomError::ServerShuttingDown => {
+                write!(f, "Server is shutting down, reconnect in a moment")
+            }
+            JoinRoomError::InvalidReconnectToken => write!(f, "Reconnect token is invalid"),
+            JoinRoomError::SeatNotHeld => {
+                write!(
+                    f,
+                    "Your player is no longer waiting for you, join the room instead"
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum HostActionError {
+    #[serde(rename = "notInRoom")]
+    NotInRoom,
+    #[serde(rename = "notHost")]
+    NotHost,
+    #[serde(rename = "playerNotInRoom")]
+    PlayerNotInRoom,
+    #[serde(rename = "cannotKickSelf")]
+    CannotKickSelf,
+    #[serde(rename = "notEnoughPlayers")]
+    NotEnoughPlayers,
+    #[serde(rename = "gameInProgress")]
+    GameInProgress,
+    #[serde(rename = "invalidSettings")]
+    InvalidSettings,
+    #[serde(rename = "roomFull")]
+    RoomFull,
+    #[serde(rename = "botNotFound")]
+    BotNotFound,
+}
+
+impl fmt::Display for HostActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HostActionError::NotInRoom => write!(f, "Not in a room"),
+            HostActionError::NotHost => write!(f, "Only the host can do this"),
+            HostActionError::PlayerNotInRoom => write!(f, "Player is not in the room"),
+            HostActionError::CannotKickSelf => write!(f, "Host cannot kick themselves"),
+            HostActionError::NotEnoughPlayers => write!(f, "Not enough players to start"),
+            HostActionError::GameInProgress => write!(f, "Game is already in progress"),
+            HostActionError::InvalidSettings => write!(f, "Invalid room settings"),
+            HostActionError::RoomFull => write!(f, "Room is full"),
+            HostActionError::BotNotFound => write!(f, "Bot is not in the room"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum AdminError {
+    #[serde(rename = "playerNotFound")]
+    PlayerNotFound,
+    #[serde(rename = "roomNotFound")]
+    RoomNotFound,
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AdminError::PlayerNotFound => write!(f, "Player is not connected or not in a room"),
+            AdminError::RoomNotFound => write!(f, "Room does not exist"),
+        }
+    }
+}
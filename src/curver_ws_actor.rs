@@ -1,48 +1,91 @@
+use std::sync::Arc;
+
 use actix::{Actor, Addr, AsyncContext, Handler, StreamHandler};
 use actix_web_actors::ws::{self, Message, ProtocolError, WebsocketContext};
-use tokio::sync::mpsc::Sender;
-use uuid::Uuid;
 
 use crate::{
+    client_sink::ClientSink,
     game::player::PlayerUuid,
-    message::{CurverMessageToReceive, CurverMessageToSend, ForwardedMessage},
+    message::{CurverMessageToReceive, CurverMessageToSend, ForwardedMessage, Frame},
+    metrics::metrics,
+    routing::Router,
 };
 
 pub struct CurverWebSocketActor {
     pub id: PlayerUuid,
-    pub internal_message_transmitter: Sender<ForwardedMessage>,
+    pub router: Router,
+    pub span: tracing::Span,
 }
 
 impl Actor for CurverWebSocketActor {
     type Context = WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let _entered = self.span.enter();
+        tracing::info!("Connected");
+        metrics().active_connections.inc();
+
+        self.router.forward(ForwardedMessage {
+            user_id: self.id,
+            address: Arc::new(ctx.address()),
+            message: CurverMessageToReceive::Connect,
+        });
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        metrics().active_connections.dec();
+    }
 }
 
 impl Handler<CurverMessageToSend> for CurverWebSocketActor {
     type Result = ();
 
     fn handle(&mut self, msg: CurverMessageToSend, ctx: &mut Self::Context) -> Self::Result {
+        metrics()
+            .messages_sent
+            .with_label_values(&[msg.message_type()])
+            .inc();
+
         // TODO: remove unwrap
         ctx.text(serde_json::to_string(&msg).unwrap())
     }
 }
 
+impl Handler<Frame> for CurverWebSocketActor {
+    type Result = ();
+
+    fn handle(&mut self, frame: Frame, ctx: &mut Self::Context) -> Self::Result {
+        for message in frame.messages() {
+            metrics()
+                .messages_sent
+                .with_label_values(&[message.message_type()])
+                .inc();
+        }
+
+        ctx.text(frame.text().clone())
+    }
+}
+
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for CurverWebSocketActor {
     fn handle(
         &mut self,
         msg: Result<actix_web_actors::ws::Message, ProtocolError>,
         ctx: &mut Self::Context,
     ) {
+        let _entered = self.span.enter();
+
         if let Ok(Message::Text(text)) = msg {
             let message_serialized = serde_json::from_str::<CurverMessageToReceive>(&text);
 
             if let Ok(message_serialized) = message_serialized {
-                self.internal_message_transmitter
-                    .try_send(ForwardedMessage {
-                        message: message_serialized,
-                        user_id: self.id,
-                        address: ctx.address(),
-                    });
+                self.router.forward(ForwardedMessage {
+                    message: message_serialized,
+                    user_id: self.id,
+                    address: Arc::new(ctx.address()),
+                });
             } else {
+                tracing::debug!(message = %text, "Faulty message");
+                metrics().faulty_messages.inc();
                 ctx.address().do_send(CurverMessageToSend::FaultyMessage {
                     message: text.to_string(),
                 });
@@ -51,15 +94,23 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for CurverWebSocketAc
     }
 
     fn finished(&mut self, ctx: &mut Self::Context) {
-        // TODO: Handle result
-        let result = self
-            .internal_message_transmitter
-            .try_send(ForwardedMessage {
-                user_id: self.id,
-                address: ctx.address(),
-                message: CurverMessageToReceive::LeaveRoom,
-            });
+        let _entered = self.span.enter();
+        tracing::info!("Disconnected");
+
+        self.router.forward(ForwardedMessage {
+            user_id: self.id,
+            address: Arc::new(ctx.address()),
+            message: CurverMessageToReceive::Disconnect,
+        });
     }
 }
 
-pub type CurverAddress = Addr<CurverWebSocketActor>;
+impl ClientSink for Addr<CurverWebSocketActor> {
+    fn do_send(&self, message: CurverMessageToSend) {
+        Addr::do_send(self, message);
+    }
+
+    fn send_frame(&self, frame: Frame) {
+        Addr::do_send(self, frame);
+    }
+}
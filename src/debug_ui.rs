@@ -5,24 +5,30 @@ use ratatui::{
     style::{Color, Style},
     widgets::{
         canvas::{Canvas, Line},
-        Block, Borders
+        Block, Borders,
     },
     Terminal,
 };
 
 use crate::{
-    constants::{MAP_HEIGHT, MAP_WIDTH},
+    config::game_settings,
     game::{player::PlayerUuid, Game, GameOutcome},
     room::RoomUuid,
 };
 
 pub struct DebugUi {
-    terminal: Option<Terminal<CrosstermBackend<Stdout>>,>
+    terminal: Option<Terminal<CrosstermBackend<Stdout>>>,
 }
 
 const MAP_PERCENTAGE_WIDTH: f64 = 0.6;
 const RUN_DEBUG_UI: bool = false;
 
+impl Default for DebugUi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DebugUi {
     pub fn new() -> Self {
         if !RUN_DEBUG_UI {
@@ -32,7 +38,9 @@ impl DebugUi {
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend).unwrap();
 
-        Self { terminal: Some(terminal) }
+        Self {
+            terminal: Some(terminal),
+        }
     }
 
     pub fn draw_game(&mut self, game: &Game) {
@@ -40,15 +48,15 @@ impl DebugUi {
             return;
         }
 
-        self.terminal.as_mut().unwrap().draw(|f| {
+        let _ = self.terminal.as_mut().unwrap().draw(|f| {
             let size = f.size();
 
             let canvas = Canvas::default()
                 .block(Block::default().title("Game").borders(Borders::ALL))
-                .x_bounds([0.0, MAP_WIDTH as f64])
-                .y_bounds([0.0, MAP_HEIGHT as f64])
+                .x_bounds([0.0, game_settings().map_width as f64])
+                .y_bounds([0.0, game_settings().map_height as f64])
                 .paint(|ctx| {
-                    for path in game.paths.values() {
+                    for path in game.simulation.paths.values() {
                         if path.nodes.len() < 2 {
                             continue;
                         }
@@ -58,9 +66,9 @@ impl DebugUi {
                             let second_line = &path.nodes[i + 1];
                             let line = Line {
                                 x1: first_line.0.into(),
-                                y1: (MAP_HEIGHT - first_line.1).into(),
+                                y1: (game_settings().map_height - first_line.1).into(),
                                 x2: second_line.0.into(),
-                                y2: (MAP_HEIGHT - second_line.1).into(),
+                                y2: (game_settings().map_height - second_line.1).into(),
                                 color: Color::LightBlue,
                             };
 
@@ -84,12 +92,12 @@ impl DebugUi {
             return;
         }
 
-        self.terminal.as_mut().unwrap().draw(|f| {
+        let _ = self.terminal.as_mut().unwrap().draw(|f| {
             let size = f.size();
 
             // Display winner Uuid in the middle of the screen in a fancy widget
             let title = match outcome {
-                GameOutcome::Winner { .. } => format!("Winner)"),
+                GameOutcome::Winner { .. } => "Winner".to_string(),
                 GameOutcome::Tie => "Draw".to_string(),
             };
 
@@ -116,7 +124,7 @@ impl DebugUi {
             return;
         }
 
-        self.terminal.as_mut().unwrap().draw(|f| {
+        let _ = self.terminal.as_mut().unwrap().draw(|f| {
             let size = f.size();
 
             let rooms: HashMap<RoomUuid, Vec<PlayerUuid>> =
@@ -155,9 +163,9 @@ impl DebugUi {
                         // Draw a line to separate rooms
                         let line = Line {
                             x1: 0.0,
-                            y1: y as f64,
+                            y1: y,
                             x2: size.width as f64 * MAP_PERCENTAGE_WIDTH * 0.5,
-                            y2: y as f64,
+                            y2: y,
                             color: Color::LightBlue,
                         };
 
@@ -192,7 +200,7 @@ impl DebugUi {
             return;
         }
 
-        self.terminal.as_mut().unwrap().clear();
+        let _ = self.terminal.as_mut().unwrap().clear();
     }
 
     pub fn clear_game(&mut self) {
@@ -200,7 +208,7 @@ impl DebugUi {
             return;
         }
 
-        self.terminal.as_mut().unwrap().draw(|f| {
+        let _ = self.terminal.as_mut().unwrap().draw(|f| {
             let size = f.size();
 
             let width = (size.width as f64 * MAP_PERCENTAGE_WIDTH) as u16;
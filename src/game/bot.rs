@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::game_settings, constants::BOT_MAX_TURN_RADIANS_PER_TICK};
+
+use super::{
+    path::{Node, Path},
+    player::{Player, PlayerUuid},
+    Players,
+};
+
+pub type Bots = HashMap<PlayerUuid, Box<dyn BotStrategy>>;
+
+/// What a bot gets to see every tick.
+pub struct BotView<'a> {
+    pub players: &'a Players,
+    pub paths: &'a HashMap<PlayerUuid, Path>,
+}
+
+/// Decides where a bot steers. Runs once per tick before players move.
+pub trait BotStrategy: Send + Sync {
+    /// Returns the new direction as a unit vector, or `None` to keep going straight.
+    fn steer(&mut self, me: &Player, view: &BotView) -> Option<(f32, f32)>;
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+pub enum BotDifficulty {
+    #[serde(rename = "easy")]
+    Easy,
+    #[default]
+    #[serde(rename = "medium")]
+    Medium,
+    #[serde(rename = "hard")]
+    Hard,
+}
+
+impl BotDifficulty {
+    pub fn create_strategy(&self) -> Box<dyn BotStrategy> {
+        match self {
+            BotDifficulty::Easy => Box::new(RandomWalker::new()),
+            BotDifficulty::Medium => Box::new(WallAvoider::new(15, 3)),
+            BotDifficulty::Hard => Box::new(WallAvoider::new(60, 9)),
+        }
+    }
+}
+
+/// Wanders around by turning randomly every now and then.
+pub struct RandomWalker {
+    turn: f32,
+}
+
+impl RandomWalker {
+    const CHANGE_TURN_PROBABILITY: f64 = 0.1;
+
+    pub fn new() -> Self {
+        Self { turn: 0.0 }
+    }
+}
+
+impl Default for RandomWalker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BotStrategy for RandomWalker {
+    fn steer(&mut self, me: &Player, _view: &BotView) -> Option<(f32, f32)> {
+        let mut rng = rand::thread_rng();
+
+        if rng.gen_bool(Self::CHANGE_TURN_PROBABILITY) {
+            self.turn =
+                rng.gen_range(-BOT_MAX_TURN_RADIANS_PER_TICK..=BOT_MAX_TURN_RADIANS_PER_TICK);
+        }
+
+        Some(rotate(me, self.turn))
+    }
+}
+
+/// Looks a number of ticks ahead in a few directions and takes the one that stays clear
+/// of walls and trails the longest, preferring to keep its current direction.
+pub struct WallAvoider {
+    look_ahead_ticks: u32,
+    candidate_count: u32,
+}
+
+impl WallAvoider {
+    pub fn new(look_ahead_ticks: u32, candidate_count: u32) -> Self {
+        Self {
+            look_ahead_ticks,
+            candidate_count: candidate_count.max(1),
+        }
+    }
+
+    fn candidate_turns(&self) -> Vec<f32> {
+        let side_count = self.candidate_count / 2;
+        let step = BOT_MAX_TURN_RADIANS_PER_TICK / side_count.max(1) as f32;
+
+        let mut turns = vec![0.0];
+        for i in 1..=side_count {
+            turns.push(step * i as f32);
+            turns.push(-step * i as f32);
+        }
+
+        turns
+    }
+
+    /// Number of ticks the bot survives when it turns once and then goes straight.
+    fn count_safe_ticks(&self, me: &Player, direction: (f32, f32), view: &BotView) -> u32 {
+        let settings = game_settings();
+        let delta_pos_per_tick = settings.delta_pos_per_tick();
+        let mut position = Node(me.x, me.y);
+
+        for tick in 0..self.look_ahead_ticks {
+            let next = Node(
+                position.0 + direction.0 * delta_pos_per_tick,
+                position.1 + direction.1 * delta_pos_per_tick,
+            );
+
+            let out_of_bounds = next.0 < 0.0
+                || next.0 > settings.map_width
+                || next.1 < 0.0
+                || next.1 > settings.map_height;
+            let segment = (position, next.clone());
+
+            if out_of_bounds
+                || view
+                    .paths
+                    .values()
+                    .any(|path| path.check_if_segment_intersects(&segment))
+            {
+                return tick;
+            }
+
+            position = next;
+        }
+
+        self.look_ahead_ticks
+    }
+}
+
+impl BotStrategy for WallAvoider {
+    fn steer(&mut self, me: &Player, view: &BotView) -> Option<(f32, f32)> {
+        // Candidates are ordered by preference, so ties keep the smallest turn
+        let mut best: Option<((f32, f32), u32)> = None;
+
+        for turn in self.candidate_turns() {
+            let direction = rotate(me, turn);
+            let safe_ticks = self.count_safe_ticks(me, direction, view);
+
+            if best.is_none_or(|(_, best_safe_ticks)| safe_ticks > best_safe_ticks) {
+                best = Some((direction, safe_ticks));
+            }
+
+            if safe_ticks == self.look_ahead_ticks && turn == 0.0 {
+                return None;
+            }
+        }
+
+        best.map(|(direction, _)| direction)
+    }
+}
+
+fn rotate(player: &Player, radians: f32) -> (f32, f32) {
+    let (sin, cos) = radians.sin_cos();
+
+    (
+        player.angle_unit_vector_x * cos - player.angle_unit_vector_y * sin,
+        player.angle_unit_vector_x * sin + player.angle_unit_vector_y * cos,
+    )
+}
@@ -1,209 +1,242 @@
+pub mod bot;
 pub mod path;
 pub mod player;
+pub mod scheduler;
+pub mod simulation;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Instant};
 
+use clap::ValueEnum;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    constants::{PLAYER_GAINED_POINTS_PER_TICK, TICK_COUNT_TO_SYNC},
-    curver_ws_actor::CurverAddress,
+    client_sink::CurverAddress,
+    config::game_settings,
     message::CurverMessageToSend,
+    rating::{Identities, PlayerRatings},
 };
 
 use self::{
-    path::{Node, Path},
+    bot::{BotDifficulty, BotView, Bots},
+    path::Path,
     player::{Player, PlayerUuid},
+    scheduler::Ticks,
+    simulation::{step, GameEvent, SimulationState, TickInput},
 };
 
 pub type Clients = HashMap<PlayerUuid, CurverAddress>;
 pub type Players = HashMap<PlayerUuid, Player>;
+pub type Paths = HashMap<PlayerUuid, Path>;
 
+/// Runs a simulation inside a room: feeds it the players' input and collects what happened
+/// for the room to broadcast. Owned by the room, which runs its ticks in between messages.
 pub struct Game {
-    pub paths: HashMap<PlayerUuid, Path>,
-    pub state: Arc<RwLock<GameState>>,
-
-    pub clients: Arc<RwLock<Clients>>,
-    pub players: Arc<RwLock<Players>>,
-    pub score_board: Arc<RwLock<HashMap<PlayerUuid, u32>>>,
-
-    tick_count: u32,
+    pub simulation: SimulationState,
+    pub span: tracing::Span,
+    ratings: Arc<RwLock<PlayerRatings>>,
+    /// Identities of the players as the game started, so leaving early does not dodge a loss.
+    identities: Identities,
+    ticks: Ticks,
+    /// Bots steering players whose client left, see `DisconnectPolicy::BotTakeover`.
+    stand_ins: Bots,
+    /// Players to eliminate on the next tick because their client is gone for good.
+    forfeits: Vec<PlayerUuid>,
+    /// Messages of the current tick, sent to everyone as one frame when it is over.
+    outbox: Vec<CurverMessageToSend>,
+    /// When the last sync went out and at which tick, to measure the actual tick rate.
+    last_sync: Option<(Instant, u32)>,
 }
 
 impl Game {
     pub fn new(
-        state: Arc<RwLock<GameState>>,
-        clients: Arc<RwLock<Clients>>,
-        players: Arc<RwLock<Players>>,
-        score_board: Arc<RwLock<HashMap<PlayerUuid, u32>>>,
+        players: Players,
+        score_board: HashMap<PlayerUuid, u32>,
+        ratings: Arc<RwLock<PlayerRatings>>,
+        ticks: Ticks,
     ) -> Game {
+        let span = tracing::info_span!("game", player_count = players.len());
+        span.in_scope(|| tracing::info!("Game started"));
+
+        let identities = ratings.read().identities_of(players.keys());
+
         Game {
-            state,
-            paths: HashMap::new(),
-            clients,
-            players,
-            tick_count: 0,
-            score_board,
+            simulation: SimulationState::new(players, score_board, game_settings().clone()),
+            span,
+            ratings,
+            identities,
+            ticks,
+            stand_ins: HashMap::new(),
+            forfeits: Vec::new(),
+            outbox: Vec::new(),
+            last_sync: None,
         }
     }
 
-    /// Returns the winner
-    pub fn tick(&mut self) -> Option<GameOutcome> {
-        let mut players_to_eliminate: Vec<PlayerUuid> = Vec::new();
+    /// Resolves with the number of ticks to run once the scheduler wakes the game up.
+    pub async fn next_ticks(&mut self) -> u32 {
+        self.ticks.next().await
+    }
 
-        for player in self.players.write().values_mut() {
-            player.calculate_new_position();
+    /// Paths go out to the clients every `tick_count_to_sync` ticks, starting with the first.
+    pub fn is_sync_tick(&self) -> bool {
+        self.simulation
+            .tick_count
+            .is_multiple_of(game_settings().tick_count_to_sync)
+    }
 
-            if player.check_if_out_of_bounds() {
-                players_to_eliminate.push(player.id);
-                continue;
-            }
+    /// Returns the outcome once the game is over.
+    pub fn tick(
+        &mut self,
+        rotations: HashMap<PlayerUuid, (f32, f32)>,
+        bots: &mut Bots,
+    ) -> Option<GameOutcome> {
+        let should_sync = self.is_sync_tick();
+
+        let input = self.collect_input(rotations, bots);
+        let (simulation, events) = step(std::mem::take(&mut self.simulation), &input);
+        self.simulation = simulation;
+
+        let mut outcome = None;
 
-            for path in self.paths.values() {
-                if path.check_collision(player) {
-                    players_to_eliminate.push(player.id);
-                    break;
+        for event in events {
+            match event {
+                GameEvent::PlayerEliminated { user_id } => {
+                    self.send_message_to_all(CurverMessageToSend::UserEliminated { user_id });
+                }
+                GameEvent::GameEnded {
+                    outcome: game_outcome,
+                } => {
+                    outcome = Some(game_outcome);
                 }
             }
-
-            Game::add_players_location_to_path(
-                &mut self.paths,
-                player.id,
-                Node(player.x, player.y),
-            );
         }
 
-        players_to_eliminate.iter().for_each(|player_id| {
-            self.eliminate_player_and_notify_all(*player_id);
-        });
-
         self.send_update_to_all();
-        self.update_score_board();
 
-        let remaining_player_count = self.players.read().len();
-
-        let outcome = match remaining_player_count {
-            0 => Some(GameOutcome::Tie),
-            1 => {
-                let winner = self.players.read().keys().next().cloned()?;
-                Some(GameOutcome::Winner { user_id: winner })
-            }
-            _ => None,
-        };
-
-        match outcome.clone() {
-            Some(outcome) => {
-                self.send_message_to_all(CurverMessageToSend::GameEnded {
-                    outcome,
-                    score_board: self.score_board.read().clone(),
-                });
+        if let Some(outcome) = outcome.clone() {
+            let rating_changes = self
+                .ratings
+                .write()
+                .apply_game_result(&self.simulation.placements(), &self.identities);
 
-                self.reset_all_players();
-                *self.state.write() = GameState::Waiting;
-                self.send_update_to_all();
-            }
-            None => (),
+            self.send_message_to_all(CurverMessageToSend::GameEnded {
+                outcome,
+                score_board: self.simulation.score_board_entries(),
+                rating_changes,
+            });
         }
 
-        if self.tick_count % TICK_COUNT_TO_SYNC == 0 {
+        if should_sync {
             self.send_sync_to_all();
         }
 
-        self.tick_count += 1;
-
         outcome
     }
 
-    // --- Scoreboard ---
-    fn update_score_board(&mut self) {
-        for player in self.players.read().values() {
-            let score = self
-                .score_board
-                .read()
-                .get(&player.id)
-                .cloned()
-                .unwrap_or(0);
-
-            self.score_board
-                .write()
-                .insert(player.id, score + PLAYER_GAINED_POINTS_PER_TICK);
+    /// Returns whether the player is still in the game. Under `Eliminate` the player keeps
+    /// driving until `forfeit` is called, so there is time to come back, but can not win
+    /// meanwhile.
+    pub fn player_left(&mut self, player_id: PlayerUuid, policy: DisconnectPolicy) -> bool {
+        if !self.simulation.players.contains_key(&player_id) {
+            return false;
         }
-    }
 
-    // --- Player Handling ---
-    pub fn rotate_player(
-        &mut self,
-        user_id: PlayerUuid,
-        angle_unit_vector_x: f32,
-        angle_unit_vector_y: f32,
-    ) {
-        if let Some(player) = self.players.write().get_mut(&user_id) {
-            player.angle_unit_vector_x = angle_unit_vector_x;
-            player.angle_unit_vector_y = angle_unit_vector_y;
-        };
+        self.simulation.departures.insert(player_id, policy);
+
+        if policy == DisconnectPolicy::BotTakeover {
+            self.stand_ins
+                .insert(player_id, BotDifficulty::default().create_strategy());
+        }
+
+        true
     }
 
-    fn eliminate_player_and_notify_all(&mut self, player_id: PlayerUuid) {
-        self.remove_player(player_id);
+    /// The player's client is back in control.
+    pub fn player_returned(&mut self, player_id: PlayerUuid) {
+        self.simulation.departures.remove(&player_id);
+        self.stand_ins.remove(&player_id);
+    }
 
-        self.send_message_to_all(CurverMessageToSend::UserEliminated { user_id: player_id });
+    pub fn forfeit(&mut self, player_id: PlayerUuid) {
+        self.forfeits.push(player_id);
     }
 
-    fn remove_player(&mut self, player_id: PlayerUuid) {
-        self.players.write().remove(&player_id);
+    /// Everything the last tick wants to send, in order.
+    pub fn take_messages(&mut self) -> Vec<CurverMessageToSend> {
+        std::mem::take(&mut self.outbox)
     }
 
-    fn add_players_location_to_path(
-        paths: &mut HashMap<PlayerUuid, Path>,
-        player_id: PlayerUuid,
-        node: Node,
-    ) {
-        if let Some(path) = paths.get_mut(&player_id) {
-            path.push(node);
-        } else {
-            let path = Path { nodes: vec![node] };
-
-            paths.insert(player_id, path);
+    // --- Player Handling ---
+    /// Players keep their direction until they rotate again, bots and stand-ins are asked
+    /// for theirs.
+    fn collect_input(
+        &mut self,
+        mut rotations: HashMap<PlayerUuid, (f32, f32)>,
+        bots: &mut Bots,
+    ) -> TickInput {
+        let view = BotView {
+            players: &self.simulation.players,
+            paths: &self.simulation.paths,
+        };
+
+        for (bot_id, strategy) in bots.iter_mut().chain(self.stand_ins.iter_mut()) {
+            let Some(bot) = view.players.get(bot_id) else {
+                continue;
+            };
+
+            if let Some(direction) = strategy.steer(bot, &view) {
+                rotations.insert(*bot_id, direction);
+            }
         }
-    }
 
-    fn reset_all_players(&mut self) {
-        for player in self.players.write().values_mut() {
-            player.x = 0.0;
-            player.y = 0.0;
-            player.angle_unit_vector_x = 0.0;
-            player.angle_unit_vector_y = 0.0;
-            player.is_ready = false;
+        TickInput {
+            rotations,
+            forfeits: std::mem::take(&mut self.forfeits),
         }
     }
 
     // --- Message Sending ---
-    fn send_sync_to_all(&self) {
+    fn send_sync_to_all(&mut self) {
         let sync = CurverMessageToSend::SyncPaths {
-            paths: self.paths.clone(),
+            paths: self.simulation.paths.clone(),
         };
 
         self.send_message_to_all(sync);
+        self.send_tick_rate_to_all();
     }
 
-    fn send_update_to_all(&self) {
+    fn send_tick_rate_to_all(&mut self) {
+        let now = Instant::now();
+        let tick_count = self.simulation.tick_count;
+
+        if let Some((synced_at, synced_tick_count)) = self.last_sync.replace((now, tick_count)) {
+            let elapsed = now.duration_since(synced_at).as_secs_f32();
+
+            if elapsed > 0.0 {
+                self.send_message_to_all(CurverMessageToSend::TickRate {
+                    target: game_settings().tick_rate,
+                    actual: (tick_count - synced_tick_count) as f32 / elapsed,
+                });
+            }
+        }
+    }
+
+    fn send_update_to_all(&mut self) {
         let update = CurverMessageToSend::Update {
-            players: self.players.read().values().cloned().collect(),
-            game_state: self.state.read().clone(),
+            players: self.simulation.players.values().cloned().collect(),
+            game_state: GameState::Started,
         };
 
         self.send_message_to_all(update);
     }
 
-    fn send_message_to_all(&self, message: CurverMessageToSend) {
-        for client in self.clients.read().values() {
-            client.do_send(message.clone());
-        }
+    fn send_message_to_all(&mut self, message: CurverMessageToSend) {
+        self.outbox.push(message);
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 #[serde(tag = "type")]
 pub enum GameOutcome {
     #[serde(rename = "winner")]
@@ -215,6 +248,34 @@ pub enum GameOutcome {
     Tie,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScoreBoardEntry {
+    pub score: u32,
+    pub nickname: Option<String>,
+    pub color: Option<String>,
+    /// Set if the player's client left the game and did not come back.
+    #[serde(default, rename = "leftGame", skip_serializing_if = "Option::is_none")]
+    pub left_game: Option<DisconnectPolicy>,
+}
+
+/// What happens to a player whose client leaves a running game.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default, ValueEnum)]
+pub enum DisconnectPolicy {
+    /// Eliminated once the reconnect grace period is over, or as soon as nobody else is left
+    #[default]
+    #[serde(rename = "eliminate")]
+    #[value(name = "eliminate")]
+    Eliminate,
+    /// Keeps driving in the last direction
+    #[serde(rename = "driveStraight")]
+    #[value(name = "driveStraight")]
+    DriveStraight,
+    /// A bot steers until the client comes back
+    #[serde(rename = "botTakeover")]
+    #[value(name = "botTakeover")]
+    BotTakeover,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum GameState {
     #[serde(rename = "waiting")]
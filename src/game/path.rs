@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::constants::DELTA_POS_PER_TICK;
+use crate::config::GameSettings;
 
 use super::player::Player;
 
@@ -9,6 +9,12 @@ pub struct Path {
     pub nodes: Vec<Node>,
 }
 
+impl Default for Path {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Path {
     pub fn new() -> Path {
         Path { nodes: Vec::new() }
@@ -18,23 +24,33 @@ impl Path {
         self.nodes.push(node);
     }
 
-    pub fn check_collision(&self, player: &Player) -> bool {
+    pub fn check_collision(&self, player: &Player, settings: &GameSettings) -> bool {
         if self.nodes.len() < 2 {
             return false;
         }
 
+        let delta_pos_per_tick = settings.delta_pos_per_tick();
         let player_nodes = (
             Node(
-                player.x - player.angle_unit_vector_x * DELTA_POS_PER_TICK,
-                player.y - player.angle_unit_vector_y * DELTA_POS_PER_TICK,
+                player.x - player.angle_unit_vector_x * delta_pos_per_tick,
+                player.y - player.angle_unit_vector_y * delta_pos_per_tick,
             ),
             Node(player.x, player.y),
         );
 
+        self.check_if_segment_intersects(&player_nodes)
+    }
+
+    /// Checks the segment against every segment of this path.
+    pub fn check_if_segment_intersects(&self, segment: &(Node, Node)) -> bool {
+        if self.nodes.len() < 2 {
+            return false;
+        }
+
         for i in 0..self.nodes.len() - 1 {
             let path_nodes = (self.nodes[i].clone(), self.nodes[i + 1].clone());
 
-            if Self::check_if_line_segments_intersect(&path_nodes, &player_nodes) {
+            if Self::check_if_line_segments_intersect(&path_nodes, segment) {
                 return true;
             }
         }
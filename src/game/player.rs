@@ -3,7 +3,10 @@ use core::fmt;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::constants::{DELTA_POS_PER_TICK, MAP_HEIGHT, MAP_WIDTH};
+use crate::{
+    config::GameSettings,
+    constants::{NICKNAME_MAX_LENGTH, PLAYER_COLORS},
+};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Player {
@@ -16,6 +19,11 @@ pub struct Player {
     pub angle_unit_vector_y: f32,
     #[serde(rename = "isReady")]
     pub is_ready: bool,
+    pub nickname: Option<String>,
+    /// Trail color assigned by the server, as a hex string like `#e6194b`
+    pub color: String,
+    #[serde(rename = "isBot")]
+    pub is_bot: bool,
 }
 
 impl Player {
@@ -34,22 +42,58 @@ impl Player {
             angle_unit_vector_x,
             angle_unit_vector_y,
             is_ready,
+            nickname: None,
+            color: PLAYER_COLORS[0].to_string(),
+            is_bot: false,
         }
     }
 
-    pub fn calculate_new_position(&mut self) {
-        self.x += self.angle_unit_vector_x * DELTA_POS_PER_TICK;
-        self.y += self.angle_unit_vector_y * DELTA_POS_PER_TICK;
+    pub fn calculate_new_position(&mut self, settings: &GameSettings) {
+        let delta_pos_per_tick = settings.delta_pos_per_tick();
+
+        self.x += self.angle_unit_vector_x * delta_pos_per_tick;
+        self.y += self.angle_unit_vector_y * delta_pos_per_tick;
     }
 
-    pub fn check_if_out_of_bounds(&self) -> bool {
-        self.x < 0.0 || self.x > MAP_WIDTH || self.y < 0.0 || self.y > MAP_HEIGHT
+    pub fn check_if_out_of_bounds(&self, settings: &GameSettings) -> bool {
+        self.x < 0.0 || self.x > settings.map_width || self.y < 0.0 || self.y > settings.map_height
+    }
+
+    /// Returns the trimmed nickname if it is acceptable.
+    pub fn validate_nickname(nickname: &str) -> Result<String, String> {
+        let nickname = nickname.trim();
+
+        if nickname.is_empty() {
+            return Err("Nickname is empty".to_string());
+        }
+
+        if nickname.chars().count() > NICKNAME_MAX_LENGTH {
+            return Err(format!(
+                "Nickname is longer than {} characters",
+                NICKNAME_MAX_LENGTH
+            ));
+        }
+
+        if !nickname
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == ' ' || c == '_' || c == '-')
+        {
+            return Err("Nickname can only contain letters, numbers, spaces, _ and -".to_string());
+        }
+
+        Ok(nickname.to_string())
     }
 }
 
 #[derive(Debug, PartialEq, Clone, Hash, Eq, Copy)]
 pub struct PlayerUuid(pub Uuid);
 
+impl Default for PlayerUuid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PlayerUuid {
     pub fn new() -> PlayerUuid {
         PlayerUuid(Uuid::new_v4())
@@ -82,6 +126,6 @@ impl<'de> Deserialize<'de> for PlayerUuid {
 
 impl fmt::Display for PlayerUuid {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0.to_string())
+        write!(f, "{}", self.0)
     }
 }
@@ -0,0 +1,220 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{
+    sync::{watch, Notify},
+    time::Instant,
+};
+
+use crate::{config::game_settings, constants::MAX_CATCH_UP_TICKS, metrics::metrics};
+
+/// Tells every game of one runtime when to tick, all of them in the same wakeup.
+/// Ticks stay aligned to the wall clock: a late wakeup runs the ticks it missed, up to
+/// `MAX_CATCH_UP_TICKS`, and skips the rest.
+#[derive(Clone)]
+pub struct GameScheduler {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    tick_count: watch::Sender<u64>,
+    game_started: Notify,
+}
+
+/// Ticks of the scheduler a game has not run yet. The scheduler only runs while a game
+/// holds on to one of these.
+pub struct Ticks {
+    tick_count: watch::Receiver<u64>,
+    last_tick_count: u64,
+}
+
+impl GameScheduler {
+    /// Has to be called from within the runtime the games should run on.
+    pub fn start() -> GameScheduler {
+        let (tick_count, _) = watch::channel(0);
+        let shared = Arc::new(Shared {
+            tick_count,
+            game_started: Notify::new(),
+        });
+
+        tokio::spawn(run(shared.clone()));
+
+        GameScheduler { shared }
+    }
+
+    pub fn subscribe(&self) -> Ticks {
+        let tick_count = self.shared.tick_count.subscribe();
+        let last_tick_count = *tick_count.borrow();
+        self.shared.game_started.notify_one();
+
+        Ticks {
+            tick_count,
+            last_tick_count,
+        }
+    }
+}
+
+impl Ticks {
+    /// Waits for the next wakeup and returns how many ticks the game should run.
+    pub async fn next(&mut self) -> u32 {
+        if self.tick_count.changed().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+
+        let tick_count = *self.tick_count.borrow_and_update();
+        let catch_up = CatchUp::for_due_ticks(tick_count - self.last_tick_count);
+        self.last_tick_count = tick_count;
+
+        // The game fell behind the other games of its runtime
+        if catch_up.skipped_ticks > 0 {
+            metrics().game_skipped_ticks.inc_by(catch_up.skipped_ticks);
+        }
+
+        catch_up.ticks as u32
+    }
+}
+
+/// How many of the due ticks run back to back, and how many are skipped.
+#[derive(Debug, PartialEq)]
+struct CatchUp {
+    ticks: u64,
+    skipped_ticks: u64,
+}
+
+impl CatchUp {
+    fn for_due_ticks(due_ticks: u64) -> CatchUp {
+        let ticks = due_ticks.min(u64::from(MAX_CATCH_UP_TICKS));
+
+        CatchUp {
+            ticks,
+            skipped_ticks: due_ticks - ticks,
+        }
+    }
+
+    /// A wakeup is due once per interval, plus once for every whole interval it is late.
+    fn for_lateness(lateness: Duration, tick_interval: Duration) -> CatchUp {
+        let late_intervals = lateness.as_nanos() / tick_interval.as_nanos().max(1);
+
+        CatchUp::for_due_ticks(1 + late_intervals as u64)
+    }
+
+    fn due_ticks(&self) -> u64 {
+        self.ticks + self.skipped_ticks
+    }
+}
+
+async fn run(shared: Arc<Shared>) {
+    let tick_interval = Duration::from_secs_f32(game_settings().ms_per_tick() / 1000.0);
+    let mut next_tick_at = Instant::now();
+
+    loop {
+        // Nothing ran in the meantime, so there is nothing to catch up on
+        if shared.tick_count.receiver_count() == 0 {
+            shared.game_started.notified().await;
+            next_tick_at = Instant::now();
+            continue;
+        }
+
+        tokio::time::sleep_until(next_tick_at).await;
+
+        let lateness = Instant::now().saturating_duration_since(next_tick_at);
+        metrics().tick_lateness.observe(lateness.as_secs_f64());
+
+        let catch_up = CatchUp::for_lateness(lateness, tick_interval);
+
+        if catch_up.ticks > 1 {
+            metrics().late_ticks.inc_by(catch_up.ticks - 1);
+        }
+
+        if catch_up.skipped_ticks > 0 {
+            metrics().skipped_ticks.inc_by(catch_up.skipped_ticks);
+            tracing::warn!(
+                skipped_ticks = catch_up.skipped_ticks,
+                lateness_ms = lateness.as_millis() as u64,
+                "Skipped ticks to catch up with the wall clock"
+            );
+        }
+
+        shared
+            .tick_count
+            .send_modify(|tick_count| *tick_count += catch_up.ticks);
+        next_tick_at += tick_interval * catch_up.due_ticks() as u32;
+
+        tracing::trace!(
+            game_count = shared.tick_count.receiver_count(),
+            ticks = catch_up.ticks,
+            "Woke up games"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK_INTERVAL: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn wakeups_on_time_run_one_tick() {
+        assert_eq!(
+            CatchUp::for_lateness(Duration::ZERO, TICK_INTERVAL),
+            CatchUp {
+                ticks: 1,
+                skipped_ticks: 0
+            }
+        );
+        assert_eq!(
+            CatchUp::for_lateness(Duration::from_millis(49), TICK_INTERVAL).ticks,
+            1
+        );
+    }
+
+    #[test]
+    fn late_wakeups_catch_up_on_missed_ticks() {
+        assert_eq!(
+            CatchUp::for_lateness(Duration::from_millis(50), TICK_INTERVAL),
+            CatchUp {
+                ticks: 2,
+                skipped_ticks: 0
+            }
+        );
+        assert_eq!(
+            CatchUp::for_lateness(Duration::from_millis(120), TICK_INTERVAL),
+            CatchUp {
+                ticks: 3,
+                skipped_ticks: 0
+            }
+        );
+    }
+
+    #[test]
+    fn very_late_wakeups_skip_what_they_cannot_catch_up_on() {
+        let catch_up = CatchUp::for_lateness(Duration::from_millis(500), TICK_INTERVAL);
+
+        assert_eq!(
+            catch_up,
+            CatchUp {
+                ticks: u64::from(MAX_CATCH_UP_TICKS),
+                skipped_ticks: 11 - u64::from(MAX_CATCH_UP_TICKS)
+            }
+        );
+        assert_eq!(catch_up.due_ticks(), 11);
+    }
+
+    #[test]
+    fn games_behind_their_runtime_run_at_most_the_catch_up_limit() {
+        assert_eq!(
+            CatchUp::for_due_ticks(0),
+            CatchUp {
+                ticks: 0,
+                skipped_ticks: 0
+            }
+        );
+        assert_eq!(
+            CatchUp::for_due_ticks(u64::from(MAX_CATCH_UP_TICKS) + 4),
+            CatchUp {
+                ticks: u64::from(MAX_CATCH_UP_TICKS),
+                skipped_ticks: 4
+            }
+        );
+    }
+}
@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+
+use crate::config::GameSettings;
+
+use super::{
+    path::{Node, Path},
+    player::{Player, PlayerUuid},
+    DisconnectPolicy, GameOutcome, Players, ScoreBoardEntry,
+};
+
+/// Everything the simulation needs to advance a game by one tick.
+/// Knows nothing about clients, so it can be driven by a room, a test or a replay.
+#[derive(Debug, Clone, Default)]
+pub struct SimulationState {
+    pub players: Players,
+    pub paths: HashMap<PlayerUuid, Path>,
+    pub score_board: HashMap<PlayerUuid, u32>,
+
+    /// Snapshot of everyone who took part, so eliminated players still show up on the scoreboard.
+    pub participants: Players,
+    /// Players eliminated in the same tick share a group, earliest eliminations first.
+    pub eliminations: Vec<Vec<PlayerUuid>>,
+    /// Players whose client left, and what became of them.
+    pub departures: HashMap<PlayerUuid, DisconnectPolicy>,
+    pub tick_count: u32,
+    /// Settings the game started with.
+    pub settings: GameSettings,
+}
+
+impl SimulationState {
+    pub fn new(
+        players: Players,
+        score_board: HashMap<PlayerUuid, u32>,
+        settings: GameSettings,
+    ) -> SimulationState {
+        SimulationState {
+            participants: players.clone(),
+            players,
+            paths: HashMap::new(),
+            score_board,
+            eliminations: Vec::new(),
+            departures: HashMap::new(),
+            tick_count: 0,
+            settings,
+        }
+    }
+
+    /// Finishing order from first to last place, players eliminated together are tied.
+    pub fn placements(&self) -> Vec<Vec<PlayerUuid>> {
+        let survivors: Vec<PlayerUuid> = self.players.keys().cloned().collect();
+
+        std::iter::once(survivors)
+            .filter(|survivors| !survivors.is_empty())
+            .chain(self.eliminations.iter().rev().cloned())
+            .collect()
+    }
+
+    pub fn score_board_entries(&self) -> HashMap<PlayerUuid, ScoreBoardEntry> {
+        self.score_board
+            .iter()
+            .map(|(player_id, score)| {
+                let participant = self.participants.get(player_id);
+
+                let entry = ScoreBoardEntry {
+                    score: *score,
+                    nickname: participant.and_then(|player| player.nickname.clone()),
+                    color: participant.map(|player| player.color.clone()),
+                    left_game: self.departures.get(player_id).cloned(),
+                };
+
+                (*player_id, entry)
+            })
+            .collect()
+    }
+}
+
+/// What the players did since the last tick.
+#[derive(Debug, Clone, Default)]
+pub struct TickInput {
+    /// New direction of a player as a unit vector
+    pub rotations: HashMap<PlayerUuid, (f32, f32)>,
+    /// Players eliminated before anyone moves, because their client is gone
+    pub forfeits: Vec<PlayerUuid>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    PlayerEliminated { user_id: PlayerUuid },
+    GameEnded { outcome: GameOutcome },
+}
+
+/// Advances the game by one tick.
+pub fn step(mut state: SimulationState, input: &TickInput) -> (SimulationState, Vec<GameEvent>) {
+    let mut events = Vec::new();
+    let mut players_to_eliminate: Vec<PlayerUuid> = input
+        .forfeits
+        .iter()
+        .filter(|player_id| state.players.remove(player_id).is_some())
+        .cloned()
+        .collect();
+
+    for (player_id, (angle_unit_vector_x, angle_unit_vector_y)) in input.rotations.iter() {
+        if let Some(player) = state.players.get_mut(player_id) {
+            player.angle_unit_vector_x = *angle_unit_vector_x;
+            player.angle_unit_vector_y = *angle_unit_vector_y;
+        }
+    }
+
+    for player in state.players.values_mut() {
+        player.calculate_new_position(&state.settings);
+    }
+
+    // Everyone is checked against the trails as they were before this tick and against each
+    // other's moves, so the order players are stored in does not change the outcome
+    let moves: Vec<(PlayerUuid, Path)> = state
+        .players
+        .values()
+        .map(|player| (player.id, last_move(player, &state.settings)))
+        .collect();
+    let mut players_off_the_map = Vec::new();
+    let mut players_crashed = Vec::new();
+
+    for player in state.players.values() {
+        if player.check_if_out_of_bounds(&state.settings) {
+            players_off_the_map.push(player.id);
+            continue;
+        }
+
+        let crashed = state
+            .paths
+            .values()
+            .any(|path| path.check_collision(player, &state.settings))
+            || moves.iter().any(|(other_id, other_move)| {
+                *other_id != player.id && other_move.check_collision(player, &state.settings)
+            });
+
+        if crashed {
+            players_crashed.push(player.id);
+        }
+    }
+
+    for player in state.players.values() {
+        if !players_off_the_map.contains(&player.id) {
+            add_location_to_path(&mut state.paths, player.id, Node(player.x, player.y));
+        }
+    }
+
+    players_off_the_map.extend(players_crashed);
+    players_off_the_map.sort_by_key(|player_id| player_id.0);
+    players_to_eliminate.extend(players_off_the_map);
+
+    for player_id in players_to_eliminate.iter() {
+        state.players.remove(player_id);
+        events.push(GameEvent::PlayerEliminated {
+            user_id: *player_id,
+        });
+    }
+
+    if !players_to_eliminate.is_empty() {
+        state.eliminations.push(players_to_eliminate);
+        forfeit_if_only_players_away_are_left(&mut state, &mut events);
+    }
+
+    for player_id in state.players.keys() {
+        *state.score_board.entry(*player_id).or_insert(0) += state.settings.points_per_tick;
+    }
+
+    let outcome = match state.players.len() {
+        0 | 1 => match state.placements().first().map(Vec::as_slice) {
+            Some([winner]) => Some(GameOutcome::Winner { user_id: *winner }),
+            _ => Some(GameOutcome::Tie),
+        },
+        _ => None,
+    };
+
+    if let Some(outcome) = outcome {
+        events.push(GameEvent::GameEnded { outcome });
+    }
+
+    state.tick_count += 1;
+
+    (state, events)
+}
+
+/// Under `DisconnectPolicy::Eliminate` players who left can not win while they are away. Once
+/// everyone else is out, they are eliminated before the players that just went out.
+fn forfeit_if_only_players_away_are_left(state: &mut SimulationState, events: &mut Vec<GameEvent>) {
+    let only_players_away_are_left = !state.players.is_empty()
+        && state
+            .players
+            .keys()
+            .all(|player_id| state.departures.get(player_id) == Some(&DisconnectPolicy::Eliminate));
+
+    if !only_players_away_are_left {
+        return;
+    }
+
+    let mut players_away: Vec<PlayerUuid> = state
+        .players
+        .drain()
+        .map(|(player_id, _)| player_id)
+        .collect();
+    players_away.sort_by_key(|player_id| player_id.0);
+
+    for player_id in players_away.iter() {
+        events.push(GameEvent::PlayerEliminated {
+            user_id: *player_id,
+        });
+    }
+
+    let last_group = state.eliminations.len() - 1;
+    state.eliminations.insert(last_group, players_away);
+}
+
+/// Where the player came from this tick and where they are now.
+fn last_move(player: &Player, settings: &GameSettings) -> Path {
+    let delta_pos_per_tick = settings.delta_pos_per_tick();
+
+    Path {
+        nodes: vec![
+            Node(
+                player.x - player.angle_unit_vector_x * delta_pos_per_tick,
+                player.y - player.angle_unit_vector_y * delta_pos_per_tick,
+            ),
+            Node(player.x, player.y),
+        ],
+    }
+}
+
+fn add_location_to_path(paths: &mut HashMap<PlayerUuid, Path>, player_id: PlayerUuid, node: Node) {
+    if let Some(path) = paths.get_mut(&player_id) {
+        path.push(node);
+    } else {
+        let path = Path { nodes: vec![node] };
+
+        paths.insert(player_id, path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// Players move one unit per tick on a 100 by 100 map.
+    fn settings() -> GameSettings {
+        GameSettings {
+            tick_rate: 10.0,
+            player_speed: 10.0,
+            points_per_tick: 1,
+            map_width: 100.0,
+            map_height: 100.0,
+            ..GameSettings::default()
+        }
+    }
+
+    fn player(x: f32, y: f32, angle_unit_vector_x: f32, angle_unit_vector_y: f32) -> Player {
+        Player::new(
+            Uuid::new_v4(),
+            x,
+            y,
+            angle_unit_vector_x,
+            angle_unit_vector_y,
+            true,
+        )
+    }
+
+    fn state_with(players: &[&Player]) -> SimulationState {
+        let players: Players = players
+            .iter()
+            .map(|player| (player.id, (*player).clone()))
+            .collect();
+
+        SimulationState::new(players, HashMap::new(), settings())
+    }
+
+    fn eliminated(events: &[GameEvent]) -> Vec<PlayerUuid> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::PlayerEliminated { user_id } => Some(*user_id),
+                GameEvent::GameEnded { .. } => None,
+            })
+            .collect()
+    }
+
+    fn outcome(events: &[GameEvent]) -> Option<GameOutcome> {
+        events.iter().find_map(|event| match event {
+            GameEvent::GameEnded { outcome } => Some(outcome.clone()),
+            GameEvent::PlayerEliminated { .. } => None,
+        })
+    }
+
+    #[test]
+    fn players_move_and_leave_a_trail() {
+        let a = player(10.0, 10.0, 1.0, 0.0);
+        let b = player(50.0, 50.0, 0.0, 1.0);
+
+        let (state, events) = step(state_with(&[&a, &b]), &TickInput::default());
+
+        assert!(events.is_empty());
+        assert_eq!(
+            (state.players[&a.id].x, state.players[&a.id].y),
+            (11.0, 10.0)
+        );
+        assert_eq!(
+            (state.players[&b.id].x, state.players[&b.id].y),
+            (50.0, 51.0)
+        );
+        assert_eq!(state.paths[&a.id].nodes, vec![Node(11.0, 10.0)]);
+        assert_eq!(state.score_board[&a.id], 1);
+        assert_eq!(state.tick_count, 1);
+    }
+
+    #[test]
+    fn rotations_apply_before_moving() {
+        let a = player(10.0, 10.0, 1.0, 0.0);
+        let b = player(50.0, 50.0, 1.0, 0.0);
+        let input = TickInput {
+            rotations: HashMap::from([(a.id, (0.0, -1.0))]),
+            ..TickInput::default()
+        };
+
+        let (state, _) = step(state_with(&[&a, &b]), &input);
+
+        assert_eq!(
+            (state.players[&a.id].x, state.players[&a.id].y),
+            (10.0, 9.0)
+        );
+        assert_eq!(state.players[&b.id].x, 51.0);
+    }
+
+    #[test]
+    fn leaving_the_map_eliminates_and_the_last_player_wins() {
+        let a = player(99.5, 10.0, 1.0, 0.0);
+        let b = player(50.0, 50.0, 0.0, 1.0);
+
+        let (state, events) = step(state_with(&[&a, &b]), &TickInput::default());
+
+        assert_eq!(eliminated(&events), vec![a.id]);
+        assert_eq!(
+            outcome(&events),
+            Some(GameOutcome::Winner { user_id: b.id })
+        );
+        assert_eq!(state.placements(), vec![vec![b.id], vec![a.id]]);
+        assert!(!state.score_board.contains_key(&a.id));
+    }
+
+    #[test]
+    fn crossing_a_trail_eliminates() {
+        let a = player(20.5, 10.0, 1.0, 0.0);
+        let b = player(50.0, 50.0, 0.0, 1.0);
+        let c = player(70.0, 70.0, 0.0, 1.0);
+        let mut state = state_with(&[&a, &b, &c]);
+        state.paths.insert(
+            b.id,
+            Path {
+                nodes: vec![Node(21.0, 0.0), Node(21.0, 20.0)],
+            },
+        );
+
+        let (state, events) = step(state, &TickInput::default());
+
+        assert_eq!(eliminated(&events), vec![a.id]);
+        assert_eq!(outcome(&events), None);
+        assert_eq!(state.players.len(), 2);
+    }
+
+    #[test]
+    fn moves_crossing_in_the_same_tick_eliminate_both_players() {
+        let a = player(9.5, 10.0, 1.0, 0.0);
+        let b = player(10.0, 9.5, 0.0, 1.0);
+        let c = player(70.0, 70.0, 0.0, 1.0);
+
+        for players in [[&a, &b, &c], [&c, &b, &a]] {
+            let (state, events) = step(state_with(&players), &TickInput::default());
+
+            let mut expected = vec![a.id, b.id];
+            expected.sort_by_key(|player_id| player_id.0);
+            assert_eq!(eliminated(&events), expected);
+            assert_eq!(
+                outcome(&events),
+                Some(GameOutcome::Winner { user_id: c.id })
+            );
+            assert_eq!(state.paths[&a.id].nodes, vec![Node(10.5, 10.0)]);
+        }
+    }
+
+    #[test]
+    fn players_away_can_not_outlast_everyone_else() {
+        let a = player(99.5, 10.0, 1.0, 0.0);
+        let b = player(50.0, 50.0, 0.0, 1.0);
+        let mut state = state_with(&[&a, &b]);
+        state.departures.insert(b.id, DisconnectPolicy::Eliminate);
+
+        let (state, events) = step(state, &TickInput::default());
+
+        assert_eq!(eliminated(&events), vec![a.id, b.id]);
+        assert_eq!(
+            outcome(&events),
+            Some(GameOutcome::Winner { user_id: a.id })
+        );
+        assert_eq!(state.placements(), vec![vec![a.id], vec![b.id]]);
+    }
+
+    #[test]
+    fn players_away_keep_playing_under_other_policies() {
+        let a = player(99.5, 10.0, 1.0, 0.0);
+        let b = player(50.0, 50.0, 0.0, 1.0);
+        let mut state = state_with(&[&a, &b]);
+        state
+            .departures
+            .insert(b.id, DisconnectPolicy::DriveStraight);
+
+        let (_, events) = step(state, &TickInput::default());
+
+        assert_eq!(eliminated(&events), vec![a.id]);
+        assert_eq!(
+            outcome(&events),
+            Some(GameOutcome::Winner { user_id: b.id })
+        );
+    }
+
+    #[test]
+    fn players_away_stay_in_while_someone_else_plays() {
+        let a = player(99.5, 10.0, 1.0, 0.0);
+        let b = player(50.0, 50.0, 0.0, 1.0);
+        let c = player(70.0, 70.0, 0.0, 1.0);
+        let mut state = state_with(&[&a, &b, &c]);
+        state.departures.insert(b.id, DisconnectPolicy::Eliminate);
+
+        let (state, events) = step(state, &TickInput::default());
+
+        assert_eq!(eliminated(&events), vec![a.id]);
+        assert_eq!(outcome(&events), None);
+        assert!(state.players.contains_key(&b.id));
+    }
+
+    #[test]
+    fn players_eliminated_together_tie() {
+        let a = player(99.5, 10.0, 1.0, 0.0);
+        let b = player(10.0, 0.5, 0.0, -1.0);
+
+        let (state, events) = step(state_with(&[&a, &b]), &TickInput::default());
+
+        assert_eq!(eliminated(&events).len(), 2);
+        assert_eq!(outcome(&events), Some(GameOutcome::Tie));
+        assert_eq!(state.placements().len(), 1);
+        assert_eq!(state.placements()[0].len(), 2);
+    }
+
+    #[test]
+    fn forfeits_eliminate_before_anyone_moves() {
+        let a = player(10.0, 10.0, 1.0, 0.0);
+        let b = player(50.0, 50.0, 0.0, 1.0);
+        let c = player(70.0, 70.0, 0.0, 1.0);
+        let input = TickInput {
+            forfeits: vec![a.id],
+            ..TickInput::default()
+        };
+
+        let (state, events) = step(state_with(&[&a, &b, &c]), &input);
+
+        assert_eq!(eliminated(&events), vec![a.id]);
+        assert_eq!(outcome(&events), None);
+        assert!(!state.paths.contains_key(&a.id));
+        assert_eq!(state.eliminations, vec![vec![a.id]]);
+    }
+
+    #[test]
+    fn forfeits_of_players_out_of_the_game_are_ignored() {
+        let a = player(10.0, 10.0, 1.0, 0.0);
+        let b = player(50.0, 50.0, 0.0, 1.0);
+        let input = TickInput {
+            forfeits: vec![PlayerUuid::new()],
+            ..TickInput::default()
+        };
+
+        let (state, events) = step(state_with(&[&a, &b]), &input);
+
+        assert!(events.is_empty());
+        assert!(state.eliminations.is_empty());
+    }
+}
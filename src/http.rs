@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use actix_web::{
+    web::{self, ServiceConfig},
+    Error, HttpRequest, HttpResponse,
+};
+use actix_web_actors::ws;
+use tokio::sync::{
+    mpsc::{self, Sender},
+    oneshot,
+};
+
+use crate::{
+    admin::{configure_admin_services, AdminCommand},
+    constants::DRAIN_CLOSE_MARGIN_SECONDS,
+    curver_ws_actor::CurverWebSocketActor,
+    game::player::PlayerUuid,
+    message::ForwardedMessage,
+    metrics::metrics,
+    room_handle::{list_public_rooms, Rooms},
+    routing::Router,
+    server::ServerHandler,
+    tcp_transport,
+    worker_pool::WorkerPool,
+};
+
+#[actix_web::get("/health")]
+async fn health() -> HttpResponse {
+    HttpResponse::Ok().finish()
+}
+
+#[actix_web::get("/rooms")]
+async fn list_rooms(app_state: web::Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok().json(list_public_rooms(&app_state.rooms))
+}
+
+#[actix_web::get("/metrics")]
+async fn prometheus_metrics(app_state: web::Data<AppState>) -> HttpResponse {
+    let body = metrics().render(&app_state.rooms, &app_state.internal_message_transmitter);
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(body)
+}
+
+#[actix_web::get("/ws")]
+async fn web_socket(
+    req: HttpRequest,
+    stream: web::Payload,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, Error> {
+    let id = PlayerUuid::new();
+    let actor = CurverWebSocketActor {
+        id,
+        router: app_state.router.clone(),
+        span: tracing::info_span!("connection", player_id = %id, transport = "websocket"),
+    };
+
+    ws::start(actor, &req, stream)
+}
+
+pub struct AppState {
+    pub(crate) internal_message_transmitter: Sender<ForwardedMessage>,
+    pub(crate) router: Router,
+    pub(crate) admin_command_transmitter: Sender<AdminCommand>,
+    pub(crate) rooms: Rooms,
+    /// The admin API is disabled without a token.
+    pub(crate) admin_token: Option<String>,
+}
+
+impl AppState {
+    /// For clients running in the same process, such as the load test.
+    pub fn router(&self) -> Router {
+        self.router.clone()
+    }
+}
+
+/// Spawns the server handler with its worker pool, and the TCP listener if an address is given.
+pub fn spawn_server(
+    tcp_address: Option<String>,
+    admin_token: Option<String>,
+    worker_count: usize,
+) -> web::Data<AppState> {
+    let (internal_message_transmitter, internal_message_receiver) =
+        mpsc::channel::<ForwardedMessage>(100);
+
+    let server_handler =
+        ServerHandler::new(internal_message_receiver, WorkerPool::new(worker_count));
+    let rooms = server_handler.rooms();
+    let router = Router::new(
+        internal_message_transmitter.clone(),
+        server_handler.room_directory(),
+        server_handler.connection_aliases(),
+        server_handler.latest_rotations(),
+    );
+    let admin_command_transmitter = server_handler.admin_commands();
+    tokio::spawn(async move { server_handler.message_handler().await });
+
+    // Scripted clients can connect over raw TCP next to the WebSocket
+    if let Some(tcp_address) = tcp_address {
+        let router = router.clone();
+
+        tokio::spawn(async move {
+            if let Err(error) = tcp_transport::listen(tcp_address, router).await {
+                tracing::error!(%error, "TCP listener stopped");
+            }
+        });
+    }
+
+    web::Data::new(AppState {
+        internal_message_transmitter,
+        router,
+        admin_command_transmitter,
+        rooms,
+        admin_token: admin_token.filter(|admin_token| !admin_token.is_empty()),
+    })
+}
+
+/// Stops opening rooms and waits until the running games are over or `timeout` has passed.
+/// Rooms that still have not closed shortly after are given up on, then this returns false.
+pub async fn drain(app_state: &AppState, timeout: Duration) -> bool {
+    let (respond, drained) = oneshot::channel();
+
+    let drain = async {
+        if app_state
+            .admin_command_transmitter
+            .send(AdminCommand::Drain { timeout, respond })
+            .await
+            .is_ok()
+        {
+            let _ = drained.await;
+        }
+    };
+
+    let margin = Duration::from_secs(DRAIN_CLOSE_MARGIN_SECONDS);
+
+    if tokio::time::timeout(timeout + margin, drain).await.is_err() {
+        tracing::warn!(
+            room_count = app_state.rooms.read().len(),
+            "Rooms did not close in time, stopping anyway"
+        );
+
+        return false;
+    }
+
+    true
+}
+
+pub fn configure_services(cfg: &mut ServiceConfig, app_state: web::Data<AppState>) {
+    if app_state.admin_token.is_some() {
+        configure_admin_services(cfg);
+    }
+
+    cfg.app_data(app_state)
+        .service(health)
+        .service(list_rooms)
+        .service(prometheus_metrics)
+        .service(web_socket);
+}
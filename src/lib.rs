@@ -1,9 +1,23 @@
+pub mod admin;
+pub mod chat;
+pub mod client_sink;
 pub mod config;
 pub mod constants;
 pub mod curver_error;
 pub mod curver_ws_actor;
 pub mod debug_ui;
 pub mod game;
+pub mod http;
+pub mod matchmaking;
 pub mod message;
+pub mod metrics;
+pub mod rating;
 pub mod room;
+pub mod room_access;
+pub mod room_code;
+pub mod room_handle;
+pub mod routing;
 pub mod server;
+pub mod tcp_transport;
+pub mod telemetry;
+pub mod worker_pool;
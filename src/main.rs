@@ -1,55 +1,30 @@
-use actix_web::{
-    web::{self, ServiceConfig},
-    Error, HttpRequest, HttpResponse,
-};
-use actix_web_actors::ws;
+use actix_web::web::ServiceConfig;
+use clap::Parser;
 use curver_backend::{
-    curver_ws_actor::CurverWebSocketActor, game::player::PlayerUuid, message::ForwardedMessage,
+    config::Config,
+    http::{configure_services, spawn_server},
 };
 use shuttle_actix_web::ShuttleActixWeb;
-use tokio::sync::mpsc::{self, Sender};
-
-#[actix_web::get("/health")]
-async fn health() -> HttpResponse {
-    HttpResponse::Ok().finish()
-}
-
-#[actix_web::get("/ws")]
-async fn web_socket(
-    req: HttpRequest,
-    stream: web::Payload,
-    app_state: web::Data<AppState>,
-) -> Result<HttpResponse, Error> {
-    let id = PlayerUuid::new();
-    let actor = CurverWebSocketActor {
-        id,
-        internal_message_transmitter: app_state.internal_message_transmitter.clone(),
-    };
-
-    let resp = ws::start(actor, &req, stream);
-    resp
-}
-
-struct AppState {
-    internal_message_transmitter: Sender<ForwardedMessage>,
-}
+use shuttle_runtime::CustomError;
 
+/// Shuttle passes no arguments, so the settings come from the environment only.
 #[shuttle_runtime::main]
 async fn main() -> ShuttleActixWeb<impl FnOnce(&mut ServiceConfig) + Send + Clone + 'static> {
-    let (internal_message_transmitter, internal_message_receiver) =
-        mpsc::channel::<ForwardedMessage>(100);
+    let config = Config::try_parse_from(["curver-backend"]).map_err(CustomError::new)?;
+    let game_settings = config.game_settings().map_err(CustomError::msg)?;
 
-    let server_handler = curver_backend::server::ServerHandler::new(internal_message_receiver);
-    tokio::spawn(async move { server_handler.message_handler().await });
+    if game_settings.install().is_err() {
+        return Err(CustomError::msg("Game settings were used before they were installed").into());
+    }
 
-    let app_state = web::Data::new(AppState {
-        internal_message_transmitter,
-    });
+    let app_state = spawn_server(
+        config.tcp_address.clone(),
+        config.admin_token.clone(),
+        config.worker_count(),
+    );
 
     let service_config = move |cfg: &mut ServiceConfig| {
-        cfg.app_data(app_state.clone())
-            .service(health)
-            .service(web_socket);
+        configure_services(cfg, app_state.clone());
     };
 
     Ok(service_config.into())
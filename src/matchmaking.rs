@@ -0,0 +1,126 @@
+use std::{collections::VecDeque, time::Instant};
+
+use crate::{
+    client_sink::CurverAddress, config::game_settings, game::player::PlayerUuid,
+    message::CurverMessageToSend,
+};
+
+pub struct QueuedPlayer {
+    pub user_id: PlayerUuid,
+    pub address: CurverAddress,
+    pub rating: f64,
+    pub queued_at: Instant,
+}
+
+/// Queue of players looking for a game. The longest waiting player is always matched first,
+/// together with the players closest to their rating.
+#[derive(Default)]
+pub struct MatchmakingQueue {
+    queue: VecDeque<QueuedPlayer>,
+}
+
+impl MatchmakingQueue {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+
+    pub fn enqueue(&mut self, user_id: PlayerUuid, address: CurverAddress, rating: f64) {
+        if self.contains(user_id) {
+            return;
+        }
+
+        self.queue.push_back(QueuedPlayer {
+            user_id,
+            address,
+            rating,
+            queued_at: Instant::now(),
+        });
+    }
+
+    pub fn remove(&mut self, user_id: PlayerUuid) -> Option<QueuedPlayer> {
+        let index = self
+            .queue
+            .iter()
+            .position(|queued_player| queued_player.user_id == user_id)?;
+
+        self.queue.remove(index)
+    }
+
+    pub fn contains(&self, user_id: PlayerUuid) -> bool {
+        self.queue
+            .iter()
+            .any(|queued_player| queued_player.user_id == user_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn front(&self) -> Option<&QueuedPlayer> {
+        self.queue.front()
+    }
+
+    pub fn pop(&mut self) -> Option<QueuedPlayer> {
+        self.queue.pop_front()
+    }
+
+    pub fn take_all(&mut self) -> Vec<QueuedPlayer> {
+        self.queue.drain(..).collect()
+    }
+
+    /// A new room is opened as soon as it can be filled, or once the longest waiting
+    /// player has waited long enough and there are at least enough players to start.
+    pub fn take_group_for_new_room(&mut self) -> Option<Vec<QueuedPlayer>> {
+        let settings = game_settings();
+        let longest_wait = self.queue.front()?.queued_at.elapsed().as_secs();
+
+        let group_size = if self.queue.len() >= settings.matchmaking_room_size {
+            settings.matchmaking_room_size
+        } else if longest_wait >= settings.matchmaking_max_wait_seconds
+            && self.queue.len() >= settings.min_players_to_start
+        {
+            self.queue.len()
+        } else {
+            return None;
+        };
+
+        let anchor_rating = self.queue.front()?.rating;
+
+        let mut by_rating_distance: Vec<usize> = (1..self.queue.len()).collect();
+        by_rating_distance.sort_by(|a, b| {
+            let distance_a = (self.queue[*a].rating - anchor_rating).abs();
+            let distance_b = (self.queue[*b].rating - anchor_rating).abs();
+
+            distance_a.total_cmp(&distance_b)
+        });
+
+        let mut picked: Vec<usize> = std::iter::once(0)
+            .chain(by_rating_distance.into_iter().take(group_size - 1))
+            .collect();
+
+        // Remove from the back so the remaining indices stay valid
+        picked.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut group: Vec<QueuedPlayer> = picked
+            .into_iter()
+            .filter_map(|index| self.queue.remove(index))
+            .collect();
+        group.reverse();
+
+        Some(group)
+    }
+
+    pub fn send_status_to_all(&self) {
+        for (position, queued_player) in self.queue.iter().enumerate() {
+            queued_player
+                .address
+                .do_send(CurverMessageToSend::MatchmakingStatus {
+                    position: position + 1,
+                    players_in_queue: self.queue.len(),
+                    waited_seconds: queued_player.queued_at.elapsed().as_secs(),
+                });
+        }
+    }
+}
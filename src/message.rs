@@ -1,16 +1,23 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use actix::Message;
+use bytestring::ByteString;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    curver_ws_actor::CurverAddress,
+    client_sink::CurverAddress,
+    curver_error::{HostActionError, JoinRoomError},
     game::{
+        bot::BotDifficulty,
         path::Path,
         player::{Player, PlayerUuid},
-        GameOutcome, GameState,
+        GameOutcome, GameState, ScoreBoardEntry,
     },
+    rating::{PlayerIdentity, RatingChange},
     room::RoomUuid,
+    room_access::{InviteToken, ReconnectToken, RoomVisibility},
+    room_code::{RoomCode, RoomIdentifier},
+    room_handle::{RoomSettings, RoomSummary},
 };
 
 pub struct ForwardedMessage {
@@ -24,16 +31,84 @@ pub struct ForwardedMessage {
 #[serde(tag = "type")]
 pub enum CurverMessageToSend {
     #[serde(rename = "joinRoomError")]
-    JoinRoomError { reason: String },
+    JoinRoomError { code: JoinRoomError, reason: String },
     #[serde(rename = "joinedRoom")]
     JoinedRoom {
         #[serde(rename = "roomId")]
         room_id: RoomUuid,
+        #[serde(rename = "roomCode")]
+        room_code: RoomCode,
         #[serde(rename = "userId")]
         user_id: PlayerUuid,
+        /// Takes the player over from a new connection if this one drops during a game.
+        #[serde(rename = "reconnectToken")]
+        reconnect_token: ReconnectToken,
     },
     #[serde(rename = "leftRoom")]
     LeftRoom,
+    #[serde(rename = "inviteCreated")]
+    InviteCreated {
+        #[serde(rename = "roomId")]
+        room_id: RoomUuid,
+        #[serde(rename = "roomCode")]
+        room_code: RoomCode,
+        #[serde(rename = "inviteToken")]
+        invite_token: InviteToken,
+    },
+    #[serde(rename = "inviteError")]
+    InviteError { reason: String },
+    #[serde(rename = "roomInfo")]
+    RoomInfo {
+        #[serde(rename = "roomId")]
+        room_id: RoomUuid,
+        #[serde(rename = "roomCode")]
+        room_code: RoomCode,
+        #[serde(rename = "hostId")]
+        host_id: Option<PlayerUuid>,
+        settings: RoomSettings,
+    },
+    #[serde(rename = "kicked")]
+    Kicked,
+    #[serde(rename = "matchmakingStatus")]
+    MatchmakingStatus {
+        position: usize,
+        #[serde(rename = "playersInQueue")]
+        players_in_queue: usize,
+        #[serde(rename = "waitedSeconds")]
+        waited_seconds: u64,
+    },
+    #[serde(rename = "matchmakingCancelled")]
+    MatchmakingCancelled,
+    #[serde(rename = "lobbySnapshot")]
+    LobbySnapshot { rooms: Vec<RoomSummary> },
+    #[serde(rename = "lobbyUpdate")]
+    LobbyUpdate {
+        updated: Vec<RoomSummary>,
+        removed: Vec<RoomUuid>,
+    },
+    #[serde(rename = "chatMessage")]
+    ChatMessage {
+        #[serde(rename = "userId")]
+        user_id: PlayerUuid,
+        text: String,
+        /// Milliseconds since the Unix epoch
+        timestamp: u64,
+    },
+    #[serde(rename = "nicknameError")]
+    NicknameError { reason: String },
+    #[serde(rename = "chatError")]
+    ChatError { reason: String },
+    #[serde(rename = "playerMuted")]
+    PlayerMuted {
+        #[serde(rename = "userId")]
+        user_id: PlayerUuid,
+        muted: bool,
+    },
+    #[serde(rename = "hostActionError")]
+    HostActionError {
+        code: HostActionError,
+        reason: String,
+    },
     #[serde(rename = "leaveRoomError")]
     LeaveRoomError { reason: String },
     #[serde(rename = "update")]
@@ -44,17 +119,40 @@ pub enum CurverMessageToSend {
     },
     #[serde(rename = "syncPaths")]
     SyncPaths { paths: HashMap<PlayerUuid, Path> },
+    /// Ticks per second the game is meant to run at and what it actually ran at since the last sync.
+    #[serde(rename = "tickRate")]
+    TickRate { target: f32, actual: f32 },
     #[serde(rename = "gameEnded")]
     GameEnded {
         outcome: GameOutcome,
         #[serde(rename = "scoreBoard")]
-        score_board: HashMap<PlayerUuid, u32>,
+        score_board: HashMap<PlayerUuid, ScoreBoardEntry>,
+        #[serde(rename = "ratingChanges")]
+        rating_changes: HashMap<PlayerUuid, RatingChange>,
     },
     #[serde(rename = "userEliminated")]
     UserEliminated {
         #[serde(rename = "userId")]
         user_id: PlayerUuid,
     },
+    #[serde(rename = "roomClosed")]
+    RoomClosed { reason: String },
+    #[serde(rename = "serverNotice")]
+    ServerNotice { text: String },
+    /// Running games may still finish until the deadline, after which the server goes away.
+    #[serde(rename = "shuttingDown")]
+    ShuttingDown {
+        #[serde(rename = "deadlineSeconds")]
+        deadline_seconds: u64,
+        #[serde(rename = "reconnectAfterSeconds")]
+        reconnect_after_seconds: u64,
+    },
+    /// The message could not be queued and was dropped, it may be sent again.
+    #[serde(rename = "serverOverloaded")]
+    ServerOverloaded {
+        #[serde(rename = "messageType")]
+        message_type: String,
+    },
     #[serde(rename = "faultyMessage")]
     FaultyMessage { message: String },
 }
@@ -64,11 +162,91 @@ pub enum CurverMessageToSend {
 #[serde(tag = "type")]
 pub enum CurverMessageToReceive {
     #[serde(rename = "createRoom")]
-    CreateRoom,
+    CreateRoom {
+        #[serde(default)]
+        visibility: RoomVisibility,
+        #[serde(default)]
+        password: Option<String>,
+        #[serde(default, rename = "maxPlayers")]
+        max_players: Option<usize>,
+    },
     #[serde(rename = "joinRoom")]
     JoinRoom {
+        /// Either the room's UUID or its room code.
+        #[serde(rename = "roomId")]
+        room_id: RoomIdentifier,
+        #[serde(default)]
+        password: Option<String>,
+        #[serde(default, rename = "inviteToken")]
+        invite_token: Option<InviteToken>,
+    },
+    /// Takes over a player whose client left a running game, see `reconnectToken`.
+    #[serde(rename = "rejoinRoom")]
+    RejoinRoom {
         #[serde(rename = "roomId")]
         room_id: RoomUuid,
+        #[serde(rename = "reconnectToken")]
+        reconnect_token: ReconnectToken,
+    },
+    /// Sent by the server to a room once a connection took over a player, never by clients.
+    #[serde(skip)]
+    ReclaimSeat,
+    /// Sent by the server to a room when a player is kicked, never by clients. Unlike leaving,
+    /// the player is out of a running game right away.
+    #[serde(skip)]
+    ForfeitSeat,
+    #[serde(rename = "createInvite")]
+    CreateInvite,
+    #[serde(rename = "setIdentity")]
+    SetIdentity { identity: PlayerIdentity },
+    #[serde(rename = "subscribeLobby")]
+    SubscribeLobby,
+    #[serde(rename = "unsubscribeLobby")]
+    UnsubscribeLobby,
+    #[serde(rename = "findMatch")]
+    FindMatch,
+    /// Sent by the connection itself when the socket opens, never by clients.
+    #[serde(skip)]
+    Connect,
+    /// Sent by the connection itself when the socket closes, never by clients.
+    #[serde(skip)]
+    Disconnect,
+    #[serde(rename = "cancelMatchmaking")]
+    CancelMatchmaking,
+    #[serde(rename = "kickPlayer")]
+    KickPlayer {
+        #[serde(rename = "userId")]
+        user_id: PlayerUuid,
+    },
+    #[serde(rename = "mutePlayer")]
+    MutePlayer {
+        #[serde(rename = "userId")]
+        user_id: PlayerUuid,
+        muted: bool,
+    },
+    #[serde(rename = "addBot")]
+    AddBot {
+        #[serde(default)]
+        difficulty: BotDifficulty,
+    },
+    #[serde(rename = "removeBot")]
+    RemoveBot {
+        #[serde(rename = "userId")]
+        user_id: PlayerUuid,
+    },
+    #[serde(rename = "lockRoom")]
+    LockRoom { locked: bool },
+    #[serde(rename = "forceStart")]
+    ForceStart,
+    #[serde(rename = "updateRoomSettings")]
+    UpdateRoomSettings {
+        #[serde(default, rename = "maxPlayers")]
+        max_players: Option<usize>,
+        #[serde(default)]
+        visibility: Option<RoomVisibility>,
+        /// An empty password removes the password.
+        #[serde(default)]
+        password: Option<String>,
     },
     #[serde(rename = "leaveRoom")]
     LeaveRoom,
@@ -79,9 +257,148 @@ pub enum CurverMessageToReceive {
         #[serde(rename = "angleUnitVectorY")]
         angle_unit_vector_y: f32,
     },
+    #[serde(rename = "setNickname")]
+    SetNickname { nickname: String },
+    #[serde(rename = "sendChat")]
+    SendChat { text: String },
     #[serde(rename = "isReady")]
     IsReady {
         #[serde(rename = "isReady")]
         is_ready: bool,
     },
 }
+
+/// Messages serialized once and shared by every client they are sent to.
+/// Several messages go out together as one `batch`.
+#[derive(Debug, Clone, Message)]
+#[rtype(result = "()")]
+pub struct Frame {
+    messages: Arc<[CurverMessageToSend]>,
+    text: ByteString,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename = "batch")]
+struct Batch<'a> {
+    messages: &'a [CurverMessageToSend],
+}
+
+impl Frame {
+    pub fn new(messages: Vec<CurverMessageToSend>) -> Frame {
+        let text = match messages.as_slice() {
+            [message] => serde_json::to_string(message),
+            messages => serde_json::to_string(&Batch { messages }),
+        }
+        .expect("messages serialize to JSON");
+
+        Frame {
+            messages: messages.into(),
+            text: text.into(),
+        }
+    }
+
+    pub fn messages(&self) -> &[CurverMessageToSend] {
+        &self.messages
+    }
+
+    pub fn text(&self) -> &ByteString {
+        &self.text
+    }
+}
+
+impl CurverMessageToSend {
+    /// Value of the `type` field, used to label metrics.
+    pub fn message_type(&self) -> &'static str {
+        match self {
+            CurverMessageToSend::JoinRoomError { .. } => "joinRoomError",
+            CurverMessageToSend::JoinedRoom { .. } => "joinedRoom",
+            CurverMessageToSend::LeftRoom => "leftRoom",
+            CurverMessageToSend::InviteCreated { .. } => "inviteCreated",
+            CurverMessageToSend::InviteError { .. } => "inviteError",
+            CurverMessageToSend::RoomInfo { .. } => "roomInfo",
+            CurverMessageToSend::Kicked => "kicked",
+            CurverMessageToSend::MatchmakingStatus { .. } => "matchmakingStatus",
+            CurverMessageToSend::MatchmakingCancelled => "matchmakingCancelled",
+            CurverMessageToSend::LobbySnapshot { .. } => "lobbySnapshot",
+            CurverMessageToSend::LobbyUpdate { .. } => "lobbyUpdate",
+            CurverMessageToSend::ChatMessage { .. } => "chatMessage",
+            CurverMessageToSend::NicknameError { .. } => "nicknameError",
+            CurverMessageToSend::ChatError { .. } => "chatError",
+            CurverMessageToSend::PlayerMuted { .. } => "playerMuted",
+            CurverMessageToSend::HostActionError { .. } => "hostActionError",
+            CurverMessageToSend::LeaveRoomError { .. } => "leaveRoomError",
+            CurverMessageToSend::Update { .. } => "update",
+            CurverMessageToSend::SyncPaths { .. } => "syncPaths",
+            CurverMessageToSend::TickRate { .. } => "tickRate",
+            CurverMessageToSend::GameEnded { .. } => "gameEnded",
+            CurverMessageToSend::UserEliminated { .. } => "userEliminated",
+            CurverMessageToSend::RoomClosed { .. } => "roomClosed",
+            CurverMessageToSend::ServerNotice { .. } => "serverNotice",
+            CurverMessageToSend::ShuttingDown { .. } => "shuttingDown",
+            CurverMessageToSend::ServerOverloaded { .. } => "serverOverloaded",
+            CurverMessageToSend::FaultyMessage { .. } => "faultyMessage",
+        }
+    }
+}
+
+impl CurverMessageToReceive {
+    /// Value of the `type` field, used to label metrics.
+    pub fn message_type(&self) -> &'static str {
+        match self {
+            CurverMessageToReceive::CreateRoom { .. } => "createRoom",
+            CurverMessageToReceive::JoinRoom { .. } => "joinRoom",
+            CurverMessageToReceive::RejoinRoom { .. } => "rejoinRoom",
+            CurverMessageToReceive::ReclaimSeat => "reclaimSeat",
+            CurverMessageToReceive::ForfeitSeat => "forfeitSeat",
+            CurverMessageToReceive::CreateInvite => "createInvite",
+            CurverMessageToReceive::SetIdentity { .. } => "setIdentity",
+            CurverMessageToReceive::SubscribeLobby => "subscribeLobby",
+            CurverMessageToReceive::UnsubscribeLobby => "unsubscribeLobby",
+            CurverMessageToReceive::FindMatch => "findMatch",
+            CurverMessageToReceive::Connect => "connect",
+            CurverMessageToReceive::Disconnect => "disconnect",
+            CurverMessageToReceive::CancelMatchmaking => "cancelMatchmaking",
+            CurverMessageToReceive::KickPlayer { .. } => "kickPlayer",
+            CurverMessageToReceive::MutePlayer { .. } => "mutePlayer",
+            CurverMessageToReceive::AddBot { .. } => "addBot",
+            CurverMessageToReceive::RemoveBot { .. } => "removeBot",
+            CurverMessageToReceive::LockRoom { .. } => "lockRoom",
+            CurverMessageToReceive::ForceStart => "forceStart",
+            CurverMessageToReceive::UpdateRoomSettings { .. } => "updateRoomSettings",
+            CurverMessageToReceive::LeaveRoom => "leaveRoom",
+            CurverMessageToReceive::Rotate { .. } => "rotate",
+            CurverMessageToReceive::SetNickname { .. } => "setNickname",
+            CurverMessageToReceive::SendChat { .. } => "sendChat",
+            CurverMessageToReceive::IsReady { .. } => "isReady",
+        }
+    }
+
+    /// Changes who is connected, in a room or ready to play. The server and the rooms both
+    /// keep track of that, so losing one of these would leave them out of sync.
+    pub fn is_lifecycle(&self) -> bool {
+        matches!(
+            self,
+            CurverMessageToReceive::Connect
+                | CurverMessageToReceive::Disconnect
+                | CurverMessageToReceive::JoinRoom { .. }
+                | CurverMessageToReceive::RejoinRoom { .. }
+                | CurverMessageToReceive::ReclaimSeat
+                | CurverMessageToReceive::ForfeitSeat
+                | CurverMessageToReceive::LeaveRoom
+                | CurverMessageToReceive::AddBot { .. }
+                | CurverMessageToReceive::RemoveBot { .. }
+                | CurverMessageToReceive::IsReady { .. }
+        )
+    }
+
+    /// Handled by the player's room alone, so connections send it there directly.
+    pub fn is_room_bound(&self) -> bool {
+        matches!(
+            self,
+            CurverMessageToReceive::Rotate { .. }
+                | CurverMessageToReceive::IsReady { .. }
+                | CurverMessageToReceive::SetNickname { .. }
+                | CurverMessageToReceive::SendChat { .. }
+        )
+    }
+}
@@ -0,0 +1,282 @@
+use std::sync::OnceLock;
+
+use prometheus::{
+    exponential_buckets, Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use tokio::sync::mpsc::Sender;
+
+use crate::{
+    game::{GameOutcome, GameState},
+    message::ForwardedMessage,
+    room_handle::Rooms,
+};
+
+/// Process wide Prometheus metrics. Gauges that describe the rooms are filled in when scraped.
+pub struct Metrics {
+    registry: Registry,
+
+    pub active_connections: IntGauge,
+    pub messages_received: IntCounterVec,
+    pub messages_sent: IntCounterVec,
+    pub messages_dropped: IntCounterVec,
+    pub faulty_messages: IntCounter,
+    pub tick_duration: Histogram,
+    pub tick_lateness: Histogram,
+    pub late_ticks: IntCounter,
+    pub skipped_ticks: IntCounter,
+    pub game_skipped_ticks: IntCounter,
+    pub games_finished: IntCounter,
+    pub game_outcomes: IntCounterVec,
+    pub room_crashes: IntCounter,
+
+    rooms: IntGaugeVec,
+    room_players: IntGauge,
+    room_players_max: IntGauge,
+    rooms_by_queue_depth: IntGaugeVec,
+    room_queue_depth_max: IntGauge,
+    server_queue_depth: IntGauge,
+}
+
+/// Upper bounds of the queue depth ranges rooms are counted in, so the number of series
+/// stays the same no matter how many rooms there are.
+const QUEUE_DEPTH_RANGES: &[(usize, &str)] =
+    &[(0, "0"), (9, "1-9"), (49, "10-49"), (usize::MAX, "50+")];
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+    fn new() -> Metrics {
+        let registry = Registry::new_custom(Some("curver".to_string()), None)
+            .expect("metrics prefix is valid");
+
+        let active_connections =
+            IntGauge::new("active_connections", "Connected clients of every transport")
+                .expect("metric is valid");
+        let messages_received = IntCounterVec::new(
+            Opts::new("messages_received_total", "Messages received from clients"),
+            &["type"],
+        )
+        .expect("metric is valid");
+        let messages_sent = IntCounterVec::new(
+            Opts::new("messages_sent_total", "Messages sent to clients"),
+            &["type"],
+        )
+        .expect("metric is valid");
+        let messages_dropped = IntCounterVec::new(
+            Opts::new(
+                "messages_dropped_total",
+                "Messages from clients dropped because a queue was full",
+            ),
+            &["type"],
+        )
+        .expect("metric is valid");
+        let faulty_messages = IntCounter::new(
+            "faulty_messages_total",
+            "Messages from clients that could not be parsed",
+        )
+        .expect("metric is valid");
+        let tick_duration = Histogram::with_opts(
+            HistogramOpts::new(
+                "tick_duration_seconds",
+                "Time spent simulating one game tick",
+            )
+            .buckets(exponential_buckets(0.000_01, 2.0, 16).expect("buckets are valid")),
+        )
+        .expect("metric is valid");
+        let tick_lateness = Histogram::with_opts(
+            HistogramOpts::new(
+                "tick_lateness_seconds",
+                "Time between when a game loop wakeup was due and when it happened",
+            )
+            .buckets(exponential_buckets(0.000_1, 2.0, 14).expect("buckets are valid")),
+        )
+        .expect("metric is valid");
+        let late_ticks = IntCounter::new(
+            "late_ticks_total",
+            "Ticks run back to back to catch up with the wall clock",
+        )
+        .expect("metric is valid");
+        let skipped_ticks = IntCounter::new(
+            "skipped_ticks_total",
+            "Ticks skipped because the game loop was too far behind",
+        )
+        .expect("metric is valid");
+        let game_skipped_ticks = IntCounter::new(
+            "game_skipped_ticks_total",
+            "Ticks a game skipped because it fell behind the other games of its worker",
+        )
+        .expect("metric is valid");
+        let games_finished = IntCounter::new("games_finished_total", "Games played to the end")
+            .expect("metric is valid");
+        let game_outcomes = IntCounterVec::new(
+            Opts::new("game_outcomes_total", "Finished games by outcome"),
+            &["outcome"],
+        )
+        .expect("metric is valid");
+        let room_crashes = IntCounter::new(
+            "room_crashes_total",
+            "Rooms removed because their task panicked",
+        )
+        .expect("metric is valid");
+        let rooms = IntGaugeVec::new(Opts::new("rooms", "Rooms by game state"), &["state"])
+            .expect("metric is valid");
+        let room_players = IntGauge::new("room_players", "Players in all rooms, bots included")
+            .expect("metric is valid");
+        let room_players_max = IntGauge::new(
+            "room_players_max",
+            "Players in the fullest room, bots included",
+        )
+        .expect("metric is valid");
+        let rooms_by_queue_depth = IntGaugeVec::new(
+            Opts::new(
+                "rooms_by_queue_depth",
+                "Rooms by how many messages wait in their channel",
+            ),
+            &["depth"],
+        )
+        .expect("metric is valid");
+        let room_queue_depth_max = IntGauge::new(
+            "room_queue_depth_max",
+            "Messages waiting in the channel of the busiest room",
+        )
+        .expect("metric is valid");
+        let server_queue_depth = IntGauge::new(
+            "server_queue_depth",
+            "Messages waiting in the server handler's channel",
+        )
+        .expect("metric is valid");
+
+        let metrics = Metrics {
+            registry,
+            active_connections,
+            messages_received,
+            messages_sent,
+            messages_dropped,
+            faulty_messages,
+            tick_duration,
+            tick_lateness,
+            late_ticks,
+            skipped_ticks,
+            game_skipped_ticks,
+            games_finished,
+            game_outcomes,
+            room_crashes,
+            rooms,
+            room_players,
+            room_players_max,
+            rooms_by_queue_depth,
+            room_queue_depth_max,
+            server_queue_depth,
+        };
+
+        metrics.register_all();
+        metrics
+    }
+
+    fn register_all(&self) {
+        let collectors: Vec<Box<dyn prometheus::core::Collector>> = vec![
+            Box::new(self.active_connections.clone()),
+            Box::new(self.messages_received.clone()),
+            Box::new(self.messages_sent.clone()),
+            Box::new(self.messages_dropped.clone()),
+            Box::new(self.faulty_messages.clone()),
+            Box::new(self.tick_duration.clone()),
+            Box::new(self.tick_lateness.clone()),
+            Box::new(self.late_ticks.clone()),
+            Box::new(self.skipped_ticks.clone()),
+            Box::new(self.game_skipped_ticks.clone()),
+            Box::new(self.games_finished.clone()),
+            Box::new(self.game_outcomes.clone()),
+            Box::new(self.room_crashes.clone()),
+            Box::new(self.rooms.clone()),
+            Box::new(self.room_players.clone()),
+            Box::new(self.room_players_max.clone()),
+            Box::new(self.rooms_by_queue_depth.clone()),
+            Box::new(self.room_queue_depth_max.clone()),
+            Box::new(self.server_queue_depth.clone()),
+        ];
+
+        for collector in collectors {
+            self.registry
+                .register(collector)
+                .expect("metric is registered once");
+        }
+    }
+
+    pub fn record_game_outcome(&self, outcome: &GameOutcome) {
+        let outcome = match outcome {
+            GameOutcome::Winner { .. } => "winner",
+            GameOutcome::Tie => "tie",
+        };
+
+        self.games_finished.inc();
+        self.game_outcomes.with_label_values(&[outcome]).inc();
+    }
+
+    /// Renders every metric in the Prometheus text format.
+    pub fn render(&self, rooms: &Rooms, server_transmitter: &Sender<ForwardedMessage>) -> String {
+        self.update_room_gauges(rooms);
+        self.server_queue_depth
+            .set(queue_depth(server_transmitter) as i64);
+
+        let mut buffer = Vec::new();
+        let _ = TextEncoder::new().encode(&self.registry.gather(), &mut buffer);
+
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+
+    fn update_room_gauges(&self, rooms: &Rooms) {
+        let mut waiting = 0;
+        let mut countdown = 0;
+        let mut started = 0;
+        let mut players = 0;
+        let mut players_max = 0;
+        let mut by_queue_depth = [0; QUEUE_DEPTH_RANGES.len()];
+        let mut queue_depth_max = 0;
+
+        for room_handle in rooms.read().values() {
+            match room_handle.game_state() {
+                GameState::Waiting => waiting += 1,
+                GameState::Countdown => countdown += 1,
+                GameState::Started => started += 1,
+            }
+
+            let player_count = room_handle.player_count();
+            players += player_count;
+            players_max = players_max.max(player_count);
+
+            let depth = queue_depth(&room_handle.transmitter);
+            queue_depth_max = queue_depth_max.max(depth);
+
+            if let Some(range) = QUEUE_DEPTH_RANGES
+                .iter()
+                .position(|(upper_bound, _)| depth <= *upper_bound)
+            {
+                by_queue_depth[range] += 1;
+            }
+        }
+
+        self.rooms.with_label_values(&["waiting"]).set(waiting);
+        self.rooms.with_label_values(&["countdown"]).set(countdown);
+        self.rooms.with_label_values(&["started"]).set(started);
+
+        self.room_players.set(players as i64);
+        self.room_players_max.set(players_max as i64);
+        self.room_queue_depth_max.set(queue_depth_max as i64);
+
+        for ((_, label), count) in QUEUE_DEPTH_RANGES.iter().zip(by_queue_depth) {
+            self.rooms_by_queue_depth
+                .with_label_values(&[label])
+                .set(count);
+        }
+    }
+}
+
+fn queue_depth(transmitter: &Sender<ForwardedMessage>) -> usize {
+    transmitter.max_capacity() - transmitter.capacity()
+}
@@ -0,0 +1,380 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc as std_mpsc,
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{INITIAL_RATING, RATING_K_FACTOR},
+    game::player::PlayerUuid,
+};
+
+/// Stable name a client picks for itself, so its rating outlives the connection.
+#[derive(Debug, Serialize, PartialEq, Clone, Hash, Eq)]
+#[serde(transparent)]
+pub struct PlayerIdentity(String);
+
+impl PlayerIdentity {
+    pub fn parse(s: &str) -> Option<PlayerIdentity> {
+        let identity = s.trim();
+
+        if identity.is_empty() || identity.len() > 64 {
+            return None;
+        }
+
+        Some(PlayerIdentity(identity.to_string()))
+    }
+}
+
+impl<'de> Deserialize<'de> for PlayerIdentity {
+    fn deserialize<D>(deserializer: D) -> Result<PlayerIdentity, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        PlayerIdentity::parse(&s).ok_or_else(|| serde::de::Error::custom("invalid identity"))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub struct RatingChange {
+    pub previous: f64,
+    pub current: f64,
+}
+
+pub type Identities = HashMap<PlayerUuid, PlayerIdentity>;
+
+/// Elo ratings of every known identity, persisted as JSON.
+/// Connections that never set an identity always play at the initial rating.
+pub struct PlayerRatings {
+    ratings: HashMap<PlayerIdentity, f64>,
+    identities: Identities,
+    /// Saving happens on its own thread, so games never wait for the disk.
+    writer: Option<std_mpsc::Sender<HashMap<PlayerIdentity, f64>>>,
+}
+
+impl PlayerRatings {
+    pub fn new() -> Self {
+        Self {
+            ratings: HashMap::new(),
+            identities: HashMap::new(),
+            writer: None,
+        }
+    }
+
+    /// Starts from scratch if the file does not exist yet.
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+
+        let ratings = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        let (writer, saves) = std_mpsc::channel();
+
+        thread::Builder::new()
+            .name("curver-ratings-writer".to_string())
+            .spawn(move || write_ratings(&path, saves))
+            .expect("ratings writer thread starts");
+
+        Self {
+            ratings,
+            identities: HashMap::new(),
+            writer: Some(writer),
+        }
+    }
+
+    pub fn set_identity(&mut self, user_id: PlayerUuid, identity: PlayerIdentity) {
+        self.identities.insert(user_id, identity);
+    }
+
+    pub fn remove_identity(&mut self, user_id: PlayerUuid) {
+        self.identities.remove(&user_id);
+    }
+
+    /// Used when a connection takes over another player.
+    pub fn move_identity(&mut self, from: PlayerUuid, to: PlayerUuid) {
+        if let Some(identity) = self.identities.remove(&from) {
+            self.identities.insert(to, identity);
+        }
+    }
+
+    pub fn rating_of(&self, user_id: PlayerUuid) -> f64 {
+        self.rating_of_identity(self.identities.get(&user_id))
+    }
+
+    fn rating_of_identity(&self, identity: Option<&PlayerIdentity>) -> f64 {
+        identity
+            .and_then(|identity| self.ratings.get(identity))
+            .cloned()
+            .unwrap_or(INITIAL_RATING)
+    }
+
+    /// Identities of the given players as of now. A game keeps these until it is over,
+    /// so players who disconnect before the end are still rated.
+    pub fn identities_of<'a>(
+        &self,
+        user_ids: impl IntoIterator<Item = &'a PlayerUuid>,
+    ) -> Identities {
+        user_ids
+            .into_iter()
+            .filter_map(|user_id| {
+                self.identities
+                    .get(user_id)
+                    .map(|identity| (*user_id, identity.clone()))
+            })
+            .collect()
+    }
+
+    /// `placements` goes from first place to last, players in the same group tied.
+    /// Every player is compared against every other player as a one-on-one Elo match.
+    pub fn apply_game_result(
+        &mut self,
+        placements: &[Vec<PlayerUuid>],
+        identities: &Identities,
+    ) -> HashMap<PlayerUuid, RatingChange> {
+        let ranked: Vec<(PlayerUuid, usize, f64)> = placements
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, group)| group.iter().map(move |user_id| (*user_id, rank)))
+            .map(|(user_id, rank)| {
+                (
+                    user_id,
+                    rank,
+                    self.rating_of_identity(identities.get(&user_id)),
+                )
+            })
+            .collect();
+
+        if ranked.len() < 2 {
+            return HashMap::new();
+        }
+
+        let k_factor = RATING_K_FACTOR / (ranked.len() - 1) as f64;
+
+        let rating_changes: HashMap<PlayerUuid, RatingChange> = ranked
+            .iter()
+            .map(|(user_id, rank, rating)| {
+                let delta: f64 = ranked
+                    .iter()
+                    .filter(|(other_id, ..)| other_id != user_id)
+                    .map(|(_, other_rank, other_rating)| {
+                        let expected = 1.0 / (1.0 + 10f64.powf((other_rating - rating) / 400.0));
+                        let actual = match rank.cmp(other_rank) {
+                            std::cmp::Ordering::Less => 1.0,
+                            std::cmp::Ordering::Equal => 0.5,
+                            std::cmp::Ordering::Greater => 0.0,
+                        };
+
+                        actual - expected
+                    })
+                    .sum();
+
+                let change = RatingChange {
+                    previous: *rating,
+                    current: rating + k_factor * delta,
+                };
+
+                (*user_id, change)
+            })
+            .collect();
+
+        for (user_id, change) in rating_changes.iter() {
+            if let Some(identity) = identities.get(user_id) {
+                self.ratings.insert(identity.clone(), change.current);
+            }
+        }
+
+        self.save();
+
+        rating_changes
+    }
+
+    fn save(&self) {
+        if let Some(writer) = &self.writer {
+            let _ = writer.send(self.ratings.clone());
+        }
+    }
+}
+
+/// Only the latest ratings are written when saves pile up. The file is replaced by renaming
+/// a temporary one, so a crash never leaves it half written.
+fn write_ratings(path: &Path, saves: std_mpsc::Receiver<HashMap<PlayerIdentity, f64>>) {
+    let temporary_path = path.with_extension("json.tmp");
+
+    while let Ok(mut ratings) = saves.recv() {
+        while let Ok(newer_ratings) = saves.try_recv() {
+            ratings = newer_ratings;
+        }
+
+        let content = match serde_json::to_string(&ratings) {
+            Ok(content) => content,
+            Err(error) => {
+                tracing::error!(%error, "Could not serialize ratings");
+                continue;
+            }
+        };
+
+        if let Err(error) =
+            fs::write(&temporary_path, content).and_then(|_| fs::rename(&temporary_path, path))
+        {
+            tracing::error!(path = %path.display(), %error, "Could not save ratings");
+        }
+    }
+}
+
+impl Default for PlayerRatings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Players with an identity, so their ratings are kept.
+    fn rated_players(count: usize) -> (PlayerRatings, Vec<PlayerUuid>) {
+        let mut ratings = PlayerRatings::new();
+        let user_ids: Vec<PlayerUuid> = (0..count).map(|_| PlayerUuid::new()).collect();
+
+        for (index, user_id) in user_ids.iter().enumerate() {
+            let identity = PlayerIdentity::parse(&format!("player-{}", index)).unwrap();
+            ratings.set_identity(*user_id, identity);
+        }
+
+        (ratings, user_ids)
+    }
+
+    fn apply(
+        ratings: &mut PlayerRatings,
+        placements: &[Vec<PlayerUuid>],
+    ) -> HashMap<PlayerUuid, RatingChange> {
+        let identities = ratings.identities_of(placements.iter().flatten());
+        ratings.apply_game_result(placements, &identities)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn winner_of_two_equal_players_takes_half_the_k_factor() {
+        let (mut ratings, ids) = rated_players(2);
+
+        let changes = apply(&mut ratings, &[vec![ids[0]], vec![ids[1]]]);
+
+        assert_close(changes[&ids[0]].previous, INITIAL_RATING);
+        assert_close(
+            changes[&ids[0]].current,
+            INITIAL_RATING + RATING_K_FACTOR / 2.0,
+        );
+        assert_close(
+            changes[&ids[1]].current,
+            INITIAL_RATING - RATING_K_FACTOR / 2.0,
+        );
+        assert_close(
+            ratings.rating_of(ids[0]),
+            INITIAL_RATING + RATING_K_FACTOR / 2.0,
+        );
+    }
+
+    #[test]
+    fn ties_between_equal_players_change_nothing() {
+        let (mut ratings, ids) = rated_players(2);
+
+        let changes = apply(&mut ratings, &[vec![ids[0], ids[1]]]);
+
+        for user_id in &ids {
+            assert_close(changes[user_id].current, INITIAL_RATING);
+        }
+    }
+
+    #[test]
+    fn k_factor_is_shared_between_opponents() {
+        let (mut ratings, ids) = rated_players(3);
+
+        let changes = apply(&mut ratings, &[vec![ids[0]], vec![ids[1]], vec![ids[2]]]);
+
+        // Each of the two one-on-one matches is worth K / 2
+        assert_close(
+            changes[&ids[0]].current,
+            INITIAL_RATING + RATING_K_FACTOR / 2.0,
+        );
+        assert_close(changes[&ids[1]].current, INITIAL_RATING);
+        assert_close(
+            changes[&ids[2]].current,
+            INITIAL_RATING - RATING_K_FACTOR / 2.0,
+        );
+    }
+
+    #[test]
+    fn tied_groups_draw_against_each_other() {
+        let (mut ratings, ids) = rated_players(3);
+
+        let changes = apply(&mut ratings, &[vec![ids[0]], vec![ids[1], ids[2]]]);
+
+        assert_close(
+            changes[&ids[0]].current,
+            INITIAL_RATING + RATING_K_FACTOR / 2.0,
+        );
+        assert_close(
+            changes[&ids[1]].current,
+            INITIAL_RATING - RATING_K_FACTOR / 4.0,
+        );
+        assert_close(
+            changes[&ids[2]].current,
+            INITIAL_RATING - RATING_K_FACTOR / 4.0,
+        );
+    }
+
+    #[test]
+    fn beating_a_weaker_player_gains_less() {
+        let (mut ratings, ids) = rated_players(2);
+        apply(&mut ratings, &[vec![ids[0]], vec![ids[1]]]);
+
+        let changes = apply(&mut ratings, &[vec![ids[0]], vec![ids[1]]]);
+        let gain = changes[&ids[0]].current - changes[&ids[0]].previous;
+        let loss = changes[&ids[1]].current - changes[&ids[1]].previous;
+
+        assert!(gain > 0.0 && gain < RATING_K_FACTOR / 2.0);
+        assert_close(gain, -loss);
+    }
+
+    #[test]
+    fn players_without_an_identity_are_rated_but_not_kept() {
+        let (mut ratings, ids) = rated_players(1);
+        let anonymous_id = PlayerUuid::new();
+
+        let changes = apply(&mut ratings, &[vec![anonymous_id], vec![ids[0]]]);
+
+        assert_close(
+            changes[&anonymous_id].current,
+            INITIAL_RATING + RATING_K_FACTOR / 2.0,
+        );
+        assert_close(ratings.rating_of(anonymous_id), INITIAL_RATING);
+        assert_close(
+            ratings.rating_of(ids[0]),
+            INITIAL_RATING - RATING_K_FACTOR / 2.0,
+        );
+    }
+
+    #[test]
+    fn games_with_fewer_than_two_players_are_not_rated() {
+        let (mut ratings, ids) = rated_players(1);
+
+        assert!(apply(&mut ratings, &[vec![ids[0]]]).is_empty());
+        assert!(apply(&mut ratings, &[]).is_empty());
+        assert_close(ratings.rating_of(ids[0]), INITIAL_RATING);
+    }
+}
@@ -1,182 +1,602 @@
 use core::fmt;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use parking_lot::RwLock;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc::Receiver;
+use tokio::sync::{mpsc::Receiver, watch, Notify};
 use uuid::Uuid;
 
 use crate::{
-    constants::{GAME_START_COUNTDOWN_SECONDS, MAP_HEIGHT, MAP_WIDTH, MS_PER_TICK},
-    curver_ws_actor::CurverAddress,
+    chat::{ChatRateLimiter, WordFilter},
+    client_sink::{broadcast, CurverAddress},
+    config::game_settings,
+    constants::{CHAT_MAX_LENGTH, PLAYER_COLORS},
     debug_ui::DebugUi,
     game::{
+        bot::{BotDifficulty, Bots},
         player::{Player, PlayerUuid},
-        Clients, Game, GameState, Players,
+        scheduler::GameScheduler,
+        Clients, DisconnectPolicy, Game, GameState, Paths, Players,
     },
     message::{CurverMessageToReceive, CurverMessageToSend, ForwardedMessage},
+    metrics::metrics,
+    rating::PlayerRatings,
+    routing::LatestRotations,
 };
 
+/// Owns everything about a room and its game. Messages and ticks are handled one after the
+/// other by the room task, so a player's input always applies to the next tick.
 pub struct Room {
     receiver: Receiver<ForwardedMessage>,
 
-    clients: Arc<RwLock<Clients>>,
-    players: Arc<RwLock<Players>>,
-    game_state: Arc<RwLock<GameState>>,
-    score_board: Arc<RwLock<HashMap<PlayerUuid, u32>>>,
+    clients: Clients,
+    /// Everyone in the room. A running game has its own copy of the players that took part.
+    players: Players,
+    game_state: GameState,
+    score_board: HashMap<PlayerUuid, u32>,
+    ratings: Arc<RwLock<PlayerRatings>>,
+    bots: Bots,
+    /// Latest direction of each player since the last tick, filled in by the router.
+    rotations: LatestRotations,
+
+    word_filter: Arc<dyn WordFilter>,
+    chat_rate_limiter: ChatRateLimiter,
+    muted: HashSet<PlayerUuid>,
+
+    game_scheduler: GameScheduler,
+    countdown_ends_at: Option<tokio::time::Instant>,
+    game: Option<Game>,
+    /// Players of the running game whose client left, until their grace period ends.
+    held_seats: HashMap<PlayerUuid, tokio::time::Instant>,
+    debug_ui: DebugUi,
+
+    snapshot: watch::Sender<RoomSnapshot>,
+    closed: Arc<Notify>,
+}
+
+/// What the room last published about itself, for the server and operators.
+/// Players, scores and paths of a running game are as of the last sync.
+#[derive(Debug, Clone)]
+pub struct RoomSnapshot {
+    pub game_state: GameState,
+    pub players: Players,
+    pub score_board: HashMap<PlayerUuid, u32>,
+    pub paths: Paths,
+    /// Players a new connection can still take over.
+    pub held_seats: HashSet<PlayerUuid>,
 }
 
 impl Room {
-    pub fn new(receiver: Receiver<ForwardedMessage>) -> Self {
-        let clients = Arc::new(RwLock::new(HashMap::new()));
-        let players = Arc::new(RwLock::new(HashMap::new()));
+    pub fn new(
+        receiver: Receiver<ForwardedMessage>,
+        ratings: Arc<RwLock<PlayerRatings>>,
+        word_filter: Arc<dyn WordFilter>,
+        game_scheduler: GameScheduler,
+        rotations: LatestRotations,
+    ) -> Self {
+        let (snapshot, _) = watch::channel(RoomSnapshot {
+            game_state: GameState::Waiting,
+            players: HashMap::new(),
+            score_board: HashMap::new(),
+            paths: HashMap::new(),
+            held_seats: HashSet::new(),
+        });
 
         Self {
             receiver,
-            clients: clients.clone(),
-            players: players.clone(),
-            game_state: Arc::new(RwLock::new(GameState::Waiting)),
-            score_board: Arc::new(RwLock::new(HashMap::new())),
+            clients: HashMap::new(),
+            players: HashMap::new(),
+            game_state: GameState::Waiting,
+            score_board: HashMap::new(),
+            ratings,
+            bots: HashMap::new(),
+            rotations,
+            word_filter,
+            chat_rate_limiter: ChatRateLimiter::new(),
+            muted: HashSet::new(),
+            game_scheduler,
+            countdown_ends_at: None,
+            game: None,
+            held_seats: HashMap::new(),
+            debug_ui: DebugUi::new(),
+            snapshot,
+            closed: Arc::new(Notify::new()),
         }
     }
 
+    /// Shared with the server so it can reject joins while a game is running
+    /// and operators can inspect the room.
+    pub fn snapshot(&self) -> watch::Receiver<RoomSnapshot> {
+        self.snapshot.subscribe()
+    }
+
+    /// Lets the server close the room even while a game is running.
+    pub fn closed(&self) -> Arc<Notify> {
+        self.closed.clone()
+    }
+
+    /// An empty room keeps running until its game ends, so players can come back until
+    /// their grace period is over and everyone who took part is rated.
     pub async fn message_handler(mut self) {
         loop {
-            if let Some(forwarded_message) = self.receiver.recv().await {
-                match forwarded_message.message {
-                    CurverMessageToReceive::JoinRoom { .. } => {
-                        self.join_room_and_notify_all(
-                            forwarded_message.user_id,
-                            forwarded_message.address,
-                        );
+            tokio::select! {
+                forwarded_message = self.receiver.recv() => {
+                    let Some(forwarded_message) = forwarded_message else {
+                        break;
+                    };
+
+                    if !self.handle_message(forwarded_message) {
+                        break;
                     }
+                }
+
+                _ = countdown(self.countdown_ends_at) => {
+                    self.start_game_and_notify_all();
+                }
 
-                    CurverMessageToReceive::LeaveRoom => {
-                        self.leave_room_and_notify_all(forwarded_message.user_id);
+                ticks = next_ticks(self.game.as_mut()) => {
+                    self.run_ticks_and_notify_all(ticks);
 
-                        if self.check_if_clients_empty() {
-                            break;
-                        }
+                    if self.check_if_abandoned() {
+                        break;
                     }
+                }
 
-                    CurverMessageToReceive::IsReady { is_ready } => {
-                        self.toggle_ready_for_user_and_notify_all(
-                            forwarded_message.user_id,
-                            is_ready,
-                        );
+                _ = grace_period(self.held_seats.values().min().copied()) => {
+                    self.release_expired_seats_and_notify_all();
+                }
 
-                        self.spawn_game_if_ready_and_notify_all().await;
-                    }
+                _ = self.closed.notified() => {
+                    break;
+                }
+            }
+        }
+    }
 
-                    CurverMessageToReceive::Rotate {
-                        angle_unit_vector_x,
-                        angle_unit_vector_y,
-                    } => {
-                        self.rotate_player(
-                            forwarded_message.user_id,
-                            angle_unit_vector_x,
-                            angle_unit_vector_y,
-                        );
-                    }
+    /// Returns whether the room keeps running.
+    fn handle_message(&mut self, forwarded_message: ForwardedMessage) -> bool {
+        match forwarded_message.message {
+            CurverMessageToReceive::JoinRoom { .. } => {
+                self.join_room_and_notify_all(forwarded_message.user_id, forwarded_message.address);
+            }
 
-                    CurverMessageToReceive::CreateRoom => {
-                        panic!("CreateRoom message should not be sent to a room");
-                    }
+            CurverMessageToReceive::ReclaimSeat => {
+                self.reclaim_seat_and_notify_all(
+                    forwarded_message.user_id,
+                    forwarded_message.address,
+                );
+            }
+
+            message @ (CurverMessageToReceive::LeaveRoom | CurverMessageToReceive::ForfeitSeat) => {
+                let hold_seat = message == CurverMessageToReceive::LeaveRoom;
+                self.leave_room_and_notify_all(forwarded_message.user_id, hold_seat);
+
+                if self.check_if_abandoned() {
+                    return false;
                 }
             }
+
+            CurverMessageToReceive::IsReady { is_ready } => {
+                self.toggle_ready_for_user_and_notify_all(forwarded_message.user_id, is_ready);
+
+                self.start_countdown_if_ready_and_notify_all();
+            }
+
+            CurverMessageToReceive::ForceStart => {
+                self.force_start_countdown_and_notify_all();
+            }
+
+            CurverMessageToReceive::AddBot { difficulty } => {
+                self.spawn_bot_and_notify_all(forwarded_message.user_id, difficulty);
+            }
+
+            CurverMessageToReceive::RemoveBot { user_id } => {
+                self.remove_bot_and_notify_all(user_id);
+            }
+
+            CurverMessageToReceive::SetNickname { nickname } => {
+                self.set_nickname_and_notify_all(forwarded_message.user_id, nickname);
+            }
+
+            CurverMessageToReceive::SendChat { text } => {
+                self.send_chat_to_all(forwarded_message.user_id, text);
+            }
+
+            CurverMessageToReceive::MutePlayer { user_id, muted } => {
+                self.mute_player_and_notify_all(user_id, muted);
+            }
+
+            // The server handler and the router take care of these
+            message @ (CurverMessageToReceive::CreateRoom { .. }
+            | CurverMessageToReceive::RejoinRoom { .. }
+            | CurverMessageToReceive::CreateInvite
+            | CurverMessageToReceive::SetIdentity { .. }
+            | CurverMessageToReceive::SubscribeLobby
+            | CurverMessageToReceive::UnsubscribeLobby
+            | CurverMessageToReceive::FindMatch
+            | CurverMessageToReceive::Connect
+            | CurverMessageToReceive::Disconnect
+            | CurverMessageToReceive::CancelMatchmaking
+            | CurverMessageToReceive::KickPlayer { .. }
+            | CurverMessageToReceive::LockRoom { .. }
+            | CurverMessageToReceive::UpdateRoomSettings { .. }
+            | CurverMessageToReceive::Rotate { .. }) => {
+                tracing::warn!(
+                    message_type = message.message_type(),
+                    "Message reached a room that should not have, dropping it"
+                );
+            }
         }
+
+        true
     }
 
     // --- Game Logic ---
-    async fn spawn_game_if_ready_and_notify_all(&mut self) {
+    fn start_countdown_if_ready_and_notify_all(&mut self) {
         if !self.check_if_ready_to_start() {
             return;
         }
 
+        self.start_countdown_and_notify_all();
+    }
+
+    /// The host can start the game without waiting for everyone to be ready.
+    fn force_start_countdown_and_notify_all(&mut self) {
+        if self.game_state != GameState::Waiting {
+            return;
+        }
+
+        if self.players.len() < game_settings().min_players_to_start {
+            return;
+        }
+
+        self.start_countdown_and_notify_all();
+    }
+
+    /// Messages keep being handled during the countdown.
+    fn start_countdown_and_notify_all(&mut self) {
         self.position_all_players();
-        *self.game_state.write() = GameState::Countdown;
+        self.game_state = GameState::Countdown;
+        self.countdown_ends_at = Some(
+            tokio::time::Instant::now() + Duration::from_secs(game_settings().countdown_seconds),
+        );
+
         self.send_update_to_all();
+    }
 
-        tokio::time::sleep(tokio::time::Duration::from_secs(
-            GAME_START_COUNTDOWN_SECONDS,
-        ))
-        .await;
+    /// Everyone stays ready, so the countdown starts again once enough players are back.
+    fn cancel_countdown_if_too_few_players(&mut self) {
+        if self.game_state != GameState::Countdown
+            || self.players.len() >= game_settings().min_players_to_start
+        {
+            return;
+        }
 
-        self.spawn_game();
-        *self.game_state.write() = GameState::Started;
+        tracing::info!(player_count = self.players.len(), "Countdown cancelled");
 
-        self.send_update_to_all();
+        self.countdown_ends_at = None;
+        self.game_state = GameState::Waiting;
     }
 
-    fn spawn_game(&self) {
-        let mut game = Game::new(
-            self.game_state.clone(),
-            self.clients.clone(),
+    /// Only players in the room when the countdown ends take part.
+    fn start_game_and_notify_all(&mut self) {
+        self.countdown_ends_at = None;
+        forget_rotations(&self.rotations, self.players.keys());
+
+        self.game = Some(Game::new(
             self.players.clone(),
             self.score_board.clone(),
-        );
+            self.ratings.clone(),
+            self.game_scheduler.subscribe(),
+        ));
+        self.game_state = GameState::Started;
+        self.debug_ui.clear_game();
 
-        tokio::spawn(async move {
-            let mut debug_ui = DebugUi::new();
-            debug_ui.clear_game();
+        self.send_update_to_all();
+    }
 
-            let outcome = loop {
-                if let Some(outcome) = game.tick() {
-                    break outcome;
-                }
+    fn run_ticks_and_notify_all(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            let Some(game) = self.game.as_mut() else {
+                return;
+            };
 
-                debug_ui.draw_game(&game);
+            let span = game.span.clone();
+            let _entered = span.enter();
+            let is_sync_tick = game.is_sync_tick();
+
+            let tick_started_at = Instant::now();
+            // A player who left may already be steering in another room
+            let rotations = game
+                .simulation
+                .players
+                .keys()
+                .filter(|player_id| !game.simulation.departures.contains_key(player_id))
+                .filter_map(|player_id| self.rotations.remove(player_id))
+                .collect();
+            let outcome = game.tick(rotations, &mut self.bots);
+            let tick_duration = tick_started_at.elapsed();
+            metrics().tick_duration.observe(tick_duration.as_secs_f64());
+
+            tracing::trace!(
+                tick = game.simulation.tick_count,
+                duration_us = tick_duration.as_micros() as u64,
+                "Tick"
+            );
+
+            if tick_duration.as_secs_f32() * 1000.0 > game_settings().ms_per_tick() {
+                tracing::warn!(
+                    tick = game.simulation.tick_count,
+                    duration_us = tick_duration.as_micros() as u64,
+                    "Tick took longer than the tick interval"
+                );
+            }
 
-                tokio::time::sleep(tokio::time::Duration::from_millis(MS_PER_TICK as u64)).await;
-            };
+            self.debug_ui.draw_game(game);
+            let mut messages = game.take_messages();
+
+            if let Some(outcome) = outcome {
+                metrics().record_game_outcome(&outcome);
+                tracing::info!(?outcome, ticks = game.simulation.tick_count, "Game ended");
+
+                self.end_game();
+                messages.push(self.update());
+            }
+
+            if is_sync_tick || self.game.is_none() {
+                self.publish_snapshot();
+            }
+
+            broadcast(self.clients.values(), messages);
+        }
+    }
+
+    /// Scores carry over to the next game, everyone else has to get ready again.
+    fn end_game(&mut self) {
+        let Some(game) = self.game.take() else {
+            return;
+        };
+
+        self.score_board = game.simulation.score_board;
+        self.game_state = GameState::Waiting;
+        self.held_seats.clear();
+        self.reset_all_players();
+        forget_rotations(&self.rotations, game.simulation.participants.keys());
+    }
+
+    // --- Disconnects ---
+    /// Returns whether the player was still in the running game. Their seat is held for
+    /// `reconnect_grace_seconds`, meanwhile the disconnect policy decides how they play on.
+    fn vacate_seat(&mut self, player_id: PlayerUuid) -> bool {
+        let policy = game_settings().disconnect_policy;
+
+        let Some(game) = self.game.as_mut() else {
+            return false;
+        };
+
+        if !game.player_left(player_id, policy) {
+            return false;
+        }
+
+        tracing::info!(player_id = %player_id, ?policy, "Player left the running game");
+
+        let grace_period = Duration::from_secs(game_settings().reconnect_grace_seconds);
+        self.held_seats
+            .insert(player_id, tokio::time::Instant::now() + grace_period);
+
+        if grace_period.is_zero() {
+            self.release_expired_seats_and_notify_all();
+        }
+
+        true
+    }
+
+    /// Returns whether the player was still in the running game, which they are out of on the
+    /// next tick.
+    fn forfeit_seat(&mut self, player_id: PlayerUuid) -> bool {
+        let Some(game) = self.game.as_mut() else {
+            return false;
+        };
+
+        if !game.simulation.players.contains_key(&player_id) {
+            return false;
+        }
+
+        tracing::info!(player_id = %player_id, "Player was removed from the running game");
+        game.forfeit(player_id);
+
+        true
+    }
+
+    fn release_expired_seats_and_notify_all(&mut self) {
+        let now = tokio::time::Instant::now();
+        let expired: Vec<PlayerUuid> = self
+            .held_seats
+            .iter()
+            .filter(|(_, grace_ends_at)| **grace_ends_at <= now)
+            .map(|(player_id, _)| *player_id)
+            .collect();
+
+        let Some(game) = self.game.as_mut() else {
+            return;
+        };
+
+        for player_id in expired {
+            self.held_seats.remove(&player_id);
+            tracing::info!(player_id = %player_id, "Player did not come back in time");
+
+            if game_settings().disconnect_policy == DisconnectPolicy::Eliminate {
+                game.forfeit(player_id);
+            }
+        }
+
+        self.publish_snapshot();
+    }
+
+    /// The server already made the connection speak for the player. If the seat is gone
+    /// by now the connection joins like anyone else.
+    fn reclaim_seat_and_notify_all(&mut self, player_id: PlayerUuid, address: CurverAddress) {
+        let Some(game) = self
+            .game
+            .as_mut()
+            .filter(|_| self.held_seats.remove(&player_id).is_some())
+        else {
+            self.join_room_and_notify_all(player_id, address);
+            return;
+        };
+
+        game.player_returned(player_id);
+        tracing::info!(player_id = %player_id, "Player took over again");
 
-            // debug_ui.display_outcome(outcome);
+        let Some(player) = game.simulation.participants.get(&player_id).cloned() else {
+            return;
+        };
+
+        address.do_send(CurverMessageToSend::SyncPaths {
+            paths: game.simulation.paths.clone(),
         });
+
+        self.add_client(player_id, address);
+        self.players.insert(player_id, player);
+        self.debug_assert_clients_match_players();
+
+        self.send_update_to_all();
     }
 
     // --- Message Handling ---
+    /// Someone joining during a game waits in the room until the next one. Someone joining
+    /// during the countdown takes part, so they get a starting position right away.
     fn join_room_and_notify_all(&mut self, user_id: PlayerUuid, address: CurverAddress) {
+        if self.clients.contains_key(&user_id) {
+            tracing::warn!(player_id = %user_id, "User is already in the room");
+            return;
+        }
+
         self.add_client(user_id, address);
         self.spawn_player(user_id);
+        self.debug_assert_clients_match_players();
+
+        if self.game_state == GameState::Countdown {
+            self.position_player(user_id);
+        }
 
         self.send_update_to_all();
     }
 
-    fn rotate_player(
-        &mut self,
-        user_id: PlayerUuid,
-        angle_unit_vector_x: f32,
-        angle_unit_vector_y: f32,
-    ) {
-        if let Some(player) = self.players.write().get_mut(&user_id) {
-            player.angle_unit_vector_x = angle_unit_vector_x;
-            player.angle_unit_vector_y = angle_unit_vector_y;
+    /// A seat in the running game is held for players who leave, but not for kicked ones.
+    fn leave_room_and_notify_all(&mut self, user_id: PlayerUuid, hold_seat: bool) {
+        if !self.clients.contains_key(&user_id) {
+            tracing::warn!(player_id = %user_id, "User is not in the room");
+            return;
         }
-    }
 
-    fn leave_room_and_notify_all(&mut self, user_id: PlayerUuid) {
+        // A running game keeps its copy of the player until they are eliminated
         self.remove_client(user_id);
+        self.players.remove(&user_id);
+        self.muted.remove(&user_id);
+        self.chat_rate_limiter.forget(user_id);
+        self.debug_assert_clients_match_players();
+
+        let still_playing = if hold_seat {
+            self.vacate_seat(user_id)
+        } else {
+            self.forfeit_seat(user_id)
+        };
+
+        if !still_playing {
+            self.send_message_to_all(CurverMessageToSend::UserEliminated { user_id });
+        }
+
+        self.cancel_countdown_if_too_few_players();
+        self.send_update_to_all();
+    }
 
-        self.send_message_to_all(CurverMessageToSend::UserEliminated { user_id })
+    /// Every client has exactly one player and vice versa, bots aside.
+    fn debug_assert_clients_match_players(&self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let human_count = self
+            .players
+            .values()
+            .filter(|player| !player.is_bot)
+            .count();
+
+        debug_assert!(
+            self.clients.len() == human_count
+                && self
+                    .clients
+                    .keys()
+                    .all(|user_id| self.players.contains_key(user_id)),
+            "Room clients and players disagree"
+        );
     }
 
     fn check_if_ready_to_start(&self) -> bool {
-        let players_lock = self.players.read();
+        if self.game_state != GameState::Waiting {
+            return false;
+        }
 
-        if players_lock.len() < 2 {
+        if self.players.len() < game_settings().min_players_to_start {
             return false;
         }
 
-        for player in players_lock.values() {
-            if !player.is_ready {
-                return false;
-            }
+        self.players.values().all(|player| player.is_ready)
+    }
+
+    // --- Chat ---
+    fn send_chat_to_all(&mut self, user_id: PlayerUuid, text: String) {
+        let Some(address) = self.clients.get(&user_id).cloned() else {
+            return;
+        };
+
+        let text = text.trim();
+
+        let error = if self.muted.contains(&user_id) {
+            Some("You are muted")
+        } else if text.is_empty() {
+            Some("Message is empty")
+        } else if text.chars().count() > CHAT_MAX_LENGTH {
+            Some("Message is too long")
+        } else if !self.chat_rate_limiter.try_acquire(user_id) {
+            Some("You are sending messages too fast")
+        } else {
+            None
+        };
+
+        if let Some(reason) = error {
+            address.do_send(CurverMessageToSend::ChatError {
+                reason: reason.to_string(),
+            });
+            return;
         }
 
-        drop(players_lock);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0);
 
-        true
+        self.send_message_to_all(CurverMessageToSend::ChatMessage {
+            user_id,
+            text: self.word_filter.filter(text),
+            timestamp,
+        });
+    }
+
+    fn mute_player_and_notify_all(&mut self, user_id: PlayerUuid, muted: bool) {
+        if muted {
+            self.muted.insert(user_id);
+        } else {
+            self.muted.remove(&user_id);
+        }
+
+        self.send_message_to_all(CurverMessageToSend::PlayerMuted { user_id, muted });
     }
 
     // --- Player Handling ---
@@ -188,57 +608,181 @@ impl Room {
             angle_unit_vector_x: 0.0,
             angle_unit_vector_y: 0.0,
             is_ready: false,
+            nickname: None,
+            color: self.pick_unused_color(),
+            is_bot: false,
         };
 
-        self.players.write().insert(player_id, player);
+        self.players.insert(player_id, player);
     }
 
-    fn position_all_players(&mut self) {
-        // Create an imaginary circle in the middle of the map
-        // and position all players on that circle randomly
+    /// Bots have a player but no client, and are always ready. Like people they take part
+    /// in the game if they are added during the countdown.
+    fn spawn_bot_and_notify_all(&mut self, bot_id: PlayerUuid, difficulty: BotDifficulty) {
+        let nickname = (1..)
+            .map(|bot_number| format!("Bot {}", bot_number))
+            .find(|nickname| !self.check_if_nickname_taken(bot_id, nickname))
+            .expect("some bot number is free");
 
-        let circle_radius = MAP_WIDTH.min(MAP_HEIGHT) * 0.4;
+        let bot = Player {
+            id: bot_id,
+            x: 0.0,
+            y: 0.0,
+            angle_unit_vector_x: 0.0,
+            angle_unit_vector_y: 0.0,
+            is_ready: true,
+            nickname: Some(nickname),
+            color: self.pick_unused_color(),
+            is_bot: true,
+        };
 
-        let mut current_angle: f32 = rand::thread_rng().gen_range(0.0..360.0);
+        self.players.insert(bot_id, bot);
+        self.bots.insert(bot_id, difficulty.create_strategy());
+
+        if self.game_state == GameState::Countdown {
+            self.position_player(bot_id);
+        }
+
+        self.send_update_to_all();
+    }
+
+    fn remove_bot_and_notify_all(&mut self, bot_id: PlayerUuid) {
+        if self.bots.remove(&bot_id).is_none() {
+            return;
+        }
+
+        self.players.remove(&bot_id);
+
+        self.cancel_countdown_if_too_few_players();
+        self.send_update_to_all();
+    }
+
+    /// Falls back to reusing colors once the palette runs out.
+    fn pick_unused_color(&self) -> String {
+        PLAYER_COLORS
+            .iter()
+            .find(|color| !self.players.values().any(|player| player.color == **color))
+            .unwrap_or(&PLAYER_COLORS[self.players.len() % PLAYER_COLORS.len()])
+            .to_string()
+    }
+
+    fn set_nickname_and_notify_all(&mut self, user_id: PlayerUuid, nickname: String) {
+        let Some(address) = self.clients.get(&user_id).cloned() else {
+            return;
+        };
+
+        let result = Player::validate_nickname(&nickname).and_then(|nickname| {
+            if self.check_if_nickname_taken(user_id, &nickname) {
+                return Err("Nickname is already taken in this room".to_string());
+            }
+
+            if let Some(player) = self.players.get_mut(&user_id) {
+                player.nickname = Some(nickname);
+            }
+
+            Ok(())
+        });
+
+        match result {
+            Ok(()) => self.send_update_to_all(),
+            Err(reason) => address.do_send(CurverMessageToSend::NicknameError { reason }),
+        }
+    }
 
-        let circle_center_x = MAP_WIDTH / 2.0;
-        let circle_center_y = MAP_HEIGHT / 2.0;
+    /// Nicknames are unique within a room, ignoring case.
+    fn check_if_nickname_taken(&self, user_id: PlayerUuid, nickname: &str) -> bool {
+        self.players.values().any(|player| {
+            player.id != user_id
+                && player
+                    .nickname
+                    .as_ref()
+                    .is_some_and(|taken| taken.to_lowercase() == nickname.to_lowercase())
+        })
+    }
 
-        for player in self.players.write().values_mut() {
-            player.x = circle_center_x + circle_radius * current_angle.to_radians().cos();
-            player.y = circle_center_y + circle_radius * current_angle.to_radians().sin();
+    fn position_all_players(&mut self) {
+        // Create an imaginary circle in the middle of the map
+        // and position all players on that circle randomly
+        let mut current_angle: f32 = rand::thread_rng().gen_range(0.0..360.0);
 
-            player.angle_unit_vector_x = (circle_center_x - player.x) / circle_radius;
-            player.angle_unit_vector_y = (circle_center_y - player.y) / circle_radius;
+        for player in self.players.values_mut() {
+            place_on_spawn_circle(player, current_angle);
 
             current_angle += rand::thread_rng().gen_range(0.0..360.0);
             current_angle %= 360.0;
         }
     }
 
+    /// Places a player on the same circle as everyone else, at a random spot.
+    fn position_player(&mut self, player_id: PlayerUuid) {
+        if let Some(player) = self.players.get_mut(&player_id) {
+            place_on_spawn_circle(player, rand::thread_rng().gen_range(0.0..360.0));
+        }
+    }
+
+    fn reset_all_players(&mut self) {
+        for player in self.players.values_mut() {
+            player.x = 0.0;
+            player.y = 0.0;
+            player.angle_unit_vector_x = 0.0;
+            player.angle_unit_vector_y = 0.0;
+            player.is_ready = player.is_bot;
+        }
+    }
+
     // --- Message Sending ---
-    fn send_update_to_all(&self) {
-        let update = CurverMessageToSend::Update {
-            players: self.players.read().values().cloned().collect(),
-            game_state: self.game_state.read().clone(),
+    /// While a game is running everyone sees the players still in it.
+    fn update(&self) -> CurverMessageToSend {
+        let players = match &self.game {
+            Some(game) => game.simulation.players.values().cloned().collect(),
+            None => self.players.values().cloned().collect(),
         };
 
-        self.send_message_to_all(update);
+        CurverMessageToSend::Update {
+            players,
+            game_state: self.game_state.clone(),
+        }
+    }
+
+    fn send_update_to_all(&self) {
+        self.publish_snapshot();
+        self.send_message_to_all(self.update());
     }
 
     fn send_message_to_all(&self, message: CurverMessageToSend) {
-        for address in self.clients.read().values() {
-            address.do_send(message.clone());
-        }
+        broadcast(self.clients.values(), vec![message]);
+    }
+
+    /// Paths are kept from the last game until the next one starts.
+    fn publish_snapshot(&self) {
+        let (players, score_board, paths) = match &self.game {
+            Some(game) => (
+                game.simulation.players.clone(),
+                game.simulation.score_board.clone(),
+                Some(game.simulation.paths.clone()),
+            ),
+            None => (self.players.clone(), self.score_board.clone(), None),
+        };
+
+        self.snapshot.send_modify(|snapshot| {
+            snapshot.game_state = self.game_state.clone();
+            snapshot.held_seats = self.held_seats.keys().cloned().collect();
+            snapshot.players = players;
+            snapshot.score_board = score_board;
+
+            if let Some(paths) = paths {
+                snapshot.paths = paths;
+            }
+        });
     }
 
     // --- Client Handling ---
     fn add_client(&mut self, user_id: PlayerUuid, address: CurverAddress) {
-        self.clients.write().insert(user_id, address);
+        self.clients.insert(user_id, address);
     }
 
     fn toggle_ready_for_user_and_notify_all(&mut self, user_id: PlayerUuid, is_ready: bool) {
-        if let Some(player) = self.players.write().get_mut(&user_id) {
+        if let Some(player) = self.players.get_mut(&user_id) {
             player.is_ready = is_ready;
         }
 
@@ -246,17 +790,66 @@ impl Room {
     }
 
     fn remove_client(&mut self, user_id: PlayerUuid) {
-        self.clients.write().remove(&user_id);
+        self.clients.remove(&user_id);
     }
 
-    fn check_if_clients_empty(&self) -> bool {
-        self.clients.read().is_empty()
+    fn check_if_abandoned(&self) -> bool {
+        self.clients.is_empty() && self.game.is_none()
+    }
+}
+
+/// Players start on a circle in the middle of the map, facing its center.
+fn place_on_spawn_circle(player: &mut Player, angle: f32) {
+    let settings = game_settings();
+    let circle_radius = settings.map_width.min(settings.map_height) * 0.4;
+
+    let circle_center_x = settings.map_width / 2.0;
+    let circle_center_y = settings.map_height / 2.0;
+
+    player.x = circle_center_x + circle_radius * angle.to_radians().cos();
+    player.y = circle_center_y + circle_radius * angle.to_radians().sin();
+
+    player.angle_unit_vector_x = (circle_center_x - player.x) / circle_radius;
+    player.angle_unit_vector_y = (circle_center_y - player.y) / circle_radius;
+}
+
+/// Rotations only count for the game they were sent in.
+fn forget_rotations<'a>(
+    rotations: &LatestRotations,
+    player_ids: impl IntoIterator<Item = &'a PlayerUuid>,
+) {
+    for player_id in player_ids {
+        rotations.remove(player_id);
+    }
+}
+
+async fn countdown(ends_at: Option<tokio::time::Instant>) {
+    match ends_at {
+        Some(ends_at) => tokio::time::sleep_until(ends_at).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn grace_period(ends_at: Option<tokio::time::Instant>) {
+    countdown(ends_at).await
+}
+
+async fn next_ticks(game: Option<&mut Game>) -> u32 {
+    match game {
+        Some(game) => game.next_ticks().await,
+        None => std::future::pending().await,
     }
 }
 
 #[derive(Debug, PartialEq, Clone, Hash, Eq, Copy)]
 pub struct RoomUuid(pub Uuid);
 
+impl Default for RoomUuid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RoomUuid {
     pub fn new() -> RoomUuid {
         RoomUuid(Uuid::new_v4())
@@ -289,6 +882,6 @@ impl<'de> Deserialize<'de> for RoomUuid {
 
 impl fmt::Display for RoomUuid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0.to_string())
+        write!(f, "{}", self.0)
     }
 }
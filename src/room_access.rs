@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::curver_error::JoinRoomError;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+pub enum RoomVisibility {
+    #[default]
+    #[serde(rename = "public")]
+    Public,
+    #[serde(rename = "private")]
+    Private,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Hash, Eq)]
+#[serde(transparent)]
+pub struct InviteToken(pub String);
+
+impl InviteToken {
+    pub fn generate() -> InviteToken {
+        InviteToken(Uuid::new_v4().simple().to_string())
+    }
+}
+
+/// Lets a new connection take over a player whose client left a running game.
+/// Every member of a room has their own.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Hash, Eq)]
+#[serde(transparent)]
+pub struct ReconnectToken(pub String);
+
+impl ReconnectToken {
+    pub fn generate() -> ReconnectToken {
+        ReconnectToken(Uuid::new_v4().simple().to_string())
+    }
+}
+
+/// Decides who may join a room. Public rooms without a password are open to anyone,
+/// everything else needs either the password or a single-use invite token.
+#[derive(Debug, Default)]
+pub struct RoomAccess {
+    pub visibility: RoomVisibility,
+    pub password: Option<String>,
+    invite_tokens: HashSet<InviteToken>,
+}
+
+impl RoomAccess {
+    pub fn new(visibility: RoomVisibility, password: Option<String>) -> Self {
+        Self {
+            visibility,
+            password: password.filter(|password| !password.is_empty()),
+            invite_tokens: HashSet::new(),
+        }
+    }
+
+    pub fn create_invite(&mut self) -> InviteToken {
+        let invite_token = InviteToken::generate();
+        self.invite_tokens.insert(invite_token.clone());
+
+        invite_token
+    }
+
+    /// Consumes the invite token if it was used to get in.
+    pub fn authorize_join(
+        &mut self,
+        password: Option<&str>,
+        invite_token: Option<&InviteToken>,
+    ) -> Result<(), JoinRoomError> {
+        if self.visibility == RoomVisibility::Public && self.password.is_none() {
+            return Ok(());
+        }
+
+        if let Some(invite_token) = invite_token {
+            if self.invite_tokens.remove(invite_token) {
+                return Ok(());
+            }
+        }
+
+        match (&self.password, password) {
+            (Some(expected), Some(given)) if expected == given => Ok(()),
+            (Some(_), Some(_)) => Err(JoinRoomError::WrongPassword),
+            (Some(_), None) if invite_token.is_none() => Err(JoinRoomError::PasswordRequired),
+            (None, _) if invite_token.is_none() => Err(JoinRoomError::InviteRequired),
+            _ => Err(JoinRoomError::InvalidInviteToken),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn private_room() -> RoomAccess {
+        RoomAccess::new(RoomVisibility::Private, None)
+    }
+
+    fn room_with_password(visibility: RoomVisibility) -> RoomAccess {
+        RoomAccess::new(visibility, Some("secret".to_string()))
+    }
+
+    #[test]
+    fn public_rooms_without_a_password_are_open() {
+        let mut access = RoomAccess::new(RoomVisibility::Public, None);
+
+        assert_eq!(access.authorize_join(None, None), Ok(()));
+        assert_eq!(access.authorize_join(Some("anything"), None), Ok(()));
+    }
+
+    #[test]
+    fn empty_passwords_count_as_none() {
+        let mut access = RoomAccess::new(RoomVisibility::Public, Some(String::new()));
+
+        assert_eq!(access.authorize_join(None, None), Ok(()));
+    }
+
+    #[test]
+    fn rooms_with_a_password_check_it() {
+        for visibility in [RoomVisibility::Public, RoomVisibility::Private] {
+            let mut access = room_with_password(visibility);
+
+            assert_eq!(access.authorize_join(Some("secret"), None), Ok(()));
+            assert_eq!(
+                access.authorize_join(None, None),
+                Err(JoinRoomError::PasswordRequired)
+            );
+            assert_eq!(
+                access.authorize_join(Some("guess"), None),
+                Err(JoinRoomError::WrongPassword)
+            );
+        }
+    }
+
+    #[test]
+    fn private_rooms_without_a_password_need_an_invite() {
+        let mut access = private_room();
+
+        assert_eq!(
+            access.authorize_join(None, None),
+            Err(JoinRoomError::InviteRequired)
+        );
+        assert_eq!(
+            access.authorize_join(Some("secret"), None),
+            Err(JoinRoomError::InviteRequired)
+        );
+
+        let invite_token = access.create_invite();
+        assert_eq!(access.authorize_join(None, Some(&invite_token)), Ok(()));
+    }
+
+    #[test]
+    fn invite_tokens_can_only_be_used_once() {
+        let mut access = private_room();
+        let invite_token = access.create_invite();
+
+        assert_eq!(access.authorize_join(None, Some(&invite_token)), Ok(()));
+        assert_eq!(
+            access.authorize_join(None, Some(&invite_token)),
+            Err(JoinRoomError::InvalidInviteToken)
+        );
+    }
+
+    #[test]
+    fn unknown_invite_tokens_are_rejected() {
+        let mut access = private_room();
+        access.create_invite();
+
+        assert_eq!(
+            access.authorize_join(None, Some(&InviteToken::generate())),
+            Err(JoinRoomError::InvalidInviteToken)
+        );
+    }
+
+    #[test]
+    fn invites_skip_the_password() {
+        let mut access = room_with_password(RoomVisibility::Private);
+        let invite_token = access.create_invite();
+
+        assert_eq!(access.authorize_join(None, Some(&invite_token)), Ok(()));
+
+        // Without a valid invite the password decides
+        assert_eq!(
+            access.authorize_join(None, Some(&invite_token)),
+            Err(JoinRoomError::InvalidInviteToken)
+        );
+        assert_eq!(
+            access.authorize_join(Some("secret"), Some(&invite_token)),
+            Ok(())
+        );
+        assert_eq!(
+            access.authorize_join(Some("guess"), Some(&invite_token)),
+            Err(JoinRoomError::WrongPassword)
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use core::fmt;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    constants::{ROOM_CODE_ALPHABET, ROOM_CODE_LENGTH},
+    room::RoomUuid,
+};
+
+/// Short, human-friendly alias of a room that can be shared over voice or typed on a phone.
+#[derive(Debug, PartialEq, Clone, Hash, Eq)]
+pub struct RoomCode(String);
+
+impl RoomCode {
+    pub fn generate() -> RoomCode {
+        let mut rng = rand::thread_rng();
+
+        let code = (0..ROOM_CODE_LENGTH)
+            .map(|_| ROOM_CODE_ALPHABET[rng.gen_range(0..ROOM_CODE_ALPHABET.len())] as char)
+            .collect();
+
+        RoomCode(code)
+    }
+
+    /// Codes are case-insensitive, so `abcdef` and `ABCDEF` point to the same room.
+    pub fn parse(s: &str) -> Option<RoomCode> {
+        let code = s.trim().to_ascii_uppercase();
+
+        if code.len() != ROOM_CODE_LENGTH || !code.bytes().all(|c| ROOM_CODE_ALPHABET.contains(&c))
+        {
+            return None;
+        }
+
+        Some(RoomCode(code))
+    }
+}
+
+impl Serialize for RoomCode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for RoomCode {
+    fn deserialize<D>(deserializer: D) -> Result<RoomCode, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        RoomCode::parse(&s).ok_or_else(|| serde::de::Error::custom("invalid room code"))
+    }
+}
+
+impl fmt::Display for RoomCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A room can be referred to either by its UUID or by its room code.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RoomIdentifier {
+    Uuid(RoomUuid),
+    Code(RoomCode),
+}
+
+impl Serialize for RoomIdentifier {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        match self {
+            RoomIdentifier::Uuid(room_id) => room_id.serialize(serializer),
+            RoomIdentifier::Code(room_code) => room_code.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RoomIdentifier {
+    fn deserialize<D>(deserializer: D) -> Result<RoomIdentifier, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        if let Ok(uuid) = Uuid::parse_str(s.trim()) {
+            return Ok(RoomIdentifier::Uuid(RoomUuid(uuid)));
+        }
+
+        RoomCode::parse(&s)
+            .map(RoomIdentifier::Code)
+            .ok_or_else(|| serde::de::Error::custom("expected a room id or a room code"))
+    }
+}
+
+impl fmt::Display for RoomIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoomIdentifier::Uuid(room_id) => write!(f, "{}", room_id),
+            RoomIdentifier::Code(room_code) => write!(f, "{}", room_code),
+        }
+    }
+}
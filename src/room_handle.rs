@@ -0,0 +1,234 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc::Sender, watch, Notify};
+
+use crate::{
+    client_sink::{broadcast, CurverAddress},
+    config::game_settings,
+    curver_error::JoinRoomError,
+    game::{player::PlayerUuid, GameState},
+    message::{CurverMessageToSend, ForwardedMessage},
+    room::{RoomSnapshot, RoomUuid},
+    room_access::{ReconnectToken, RoomAccess, RoomVisibility},
+    room_code::RoomCode,
+};
+
+/// Everything the server knows about a room without asking the room task.
+pub struct RoomHandle {
+    pub id: RoomUuid,
+    pub transmitter: Sender<ForwardedMessage>,
+    pub code: RoomCode,
+    pub access: RoomAccess,
+    pub max_players: usize,
+    pub locked: bool,
+    pub host: Option<PlayerUuid>,
+    pub members: HashMap<PlayerUuid, CurverAddress>,
+    pub bots: HashSet<PlayerUuid>,
+    pub snapshot: watch::Receiver<RoomSnapshot>,
+    pub closed: Arc<Notify>,
+    /// Handed out to every member, kept for as long as the room exists.
+    pub reconnect_tokens: HashMap<PlayerUuid, ReconnectToken>,
+    /// Tokens of kicked players, which no longer get their seat back.
+    pub revoked_reconnect_tokens: HashSet<ReconnectToken>,
+}
+
+impl RoomHandle {
+    pub fn check_if_joinable(&self) -> Result<(), JoinRoomError> {
+        if self.game_state() != GameState::Waiting {
+            return Err(JoinRoomError::GameInProgress);
+        }
+
+        if self.locked {
+            return Err(JoinRoomError::RoomLocked);
+        }
+
+        if self.player_count() >= self.max_players {
+            return Err(JoinRoomError::RoomFull);
+        }
+
+        Ok(())
+    }
+
+    /// Bots take up a slot just like members do.
+    pub fn player_count(&self) -> usize {
+        self.members.len() + self.bots.len()
+    }
+
+    pub fn add_member(&mut self, user_id: PlayerUuid, address: CurverAddress) {
+        self.members.insert(user_id, address);
+
+        if self.host.is_none() {
+            self.host = Some(user_id);
+        }
+    }
+
+    /// Hands the host role to another member if the host is the one leaving.
+    pub fn remove_member(&mut self, user_id: PlayerUuid) {
+        self.members.remove(&user_id);
+
+        if self.host == Some(user_id) {
+            self.host = self.members.keys().next().cloned();
+        }
+    }
+
+    pub fn reconnect_token(&mut self, user_id: PlayerUuid) -> ReconnectToken {
+        self.reconnect_tokens
+            .entry(user_id)
+            .or_insert_with(ReconnectToken::generate)
+            .clone()
+    }
+
+    pub fn revoke_reconnect_token(&mut self, user_id: PlayerUuid) {
+        if let Some(reconnect_token) = self.reconnect_tokens.remove(&user_id) {
+            self.revoked_reconnect_tokens.insert(reconnect_token);
+        }
+    }
+
+    /// The player a reconnect token belongs to.
+    pub fn seat_of(&self, reconnect_token: &ReconnectToken) -> Option<PlayerUuid> {
+        self.reconnect_tokens
+            .iter()
+            .find(|(_, token)| *token == reconnect_token)
+            .map(|(user_id, _)| *user_id)
+    }
+
+    /// As of the room's last snapshot, so the room may already be further along.
+    pub fn game_state(&self) -> GameState {
+        self.snapshot.borrow().game_state.clone()
+    }
+
+    pub fn settings(&self) -> RoomSettings {
+        RoomSettings {
+            max_players: self.max_players,
+            visibility: self.access.visibility,
+            locked: self.locked,
+            has_password: self.access.password.is_some(),
+        }
+    }
+
+    /// Stops the room task, a running game ends without a result.
+    pub fn close(&self) {
+        self.closed.notify_one();
+    }
+
+    pub fn entry(&self) -> RoomEntry {
+        RoomEntry {
+            room_id: self.id,
+            transmitter: self.transmitter.clone(),
+        }
+    }
+
+    pub fn is_listed(&self) -> bool {
+        self.access.visibility == RoomVisibility::Public
+    }
+
+    pub fn summary(&self) -> RoomSummary {
+        let settings = game_settings();
+
+        RoomSummary {
+            room_id: self.id,
+            room_code: self.code.clone(),
+            player_count: self.player_count(),
+            max_players: self.max_players,
+            game_state: self.game_state(),
+            locked: self.locked,
+            has_password: self.access.password.is_some(),
+            map: MapInfo {
+                width: settings.map_width,
+                height: settings.map_height,
+            },
+            rules: GameRules {
+                min_players_to_start: settings.min_players_to_start,
+                countdown_seconds: settings.countdown_seconds,
+                tick_rate: settings.tick_rate,
+            },
+        }
+    }
+
+    // --- Message Sending ---
+    pub fn send_room_info_to_all(&self) {
+        self.send_message_to_all(CurverMessageToSend::RoomInfo {
+            room_id: self.id,
+            room_code: self.code.clone(),
+            host_id: self.host,
+            settings: self.settings(),
+        });
+    }
+
+    pub fn send_message_to_all(&self, message: CurverMessageToSend) {
+        broadcast(self.members.values(), vec![message]);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoomSettings {
+    #[serde(rename = "maxPlayers")]
+    pub max_players: usize,
+    pub visibility: RoomVisibility,
+    pub locked: bool,
+    #[serde(rename = "hasPassword")]
+    pub has_password: bool,
+}
+
+/// What the lobby browser shows about a public room.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RoomSummary {
+    #[serde(rename = "roomId")]
+    pub room_id: RoomUuid,
+    #[serde(rename = "roomCode")]
+    pub room_code: RoomCode,
+    #[serde(rename = "playerCount")]
+    pub player_count: usize,
+    #[serde(rename = "maxPlayers")]
+    pub max_players: usize,
+    #[serde(rename = "gameState")]
+    pub game_state: GameState,
+    pub locked: bool,
+    #[serde(rename = "hasPassword")]
+    pub has_password: bool,
+    pub map: MapInfo,
+    pub rules: GameRules,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct MapInfo {
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct GameRules {
+    #[serde(rename = "minPlayersToStart")]
+    pub min_players_to_start: usize,
+    #[serde(rename = "countdownSeconds")]
+    pub countdown_seconds: u64,
+    #[serde(rename = "tickRate")]
+    pub tick_rate: f32,
+}
+
+pub type Rooms = Arc<RwLock<HashMap<RoomUuid, RoomHandle>>>;
+
+/// The room each player is in. Only the server handler writes to it.
+pub type RoomDirectory = Arc<DashMap<PlayerUuid, RoomEntry>>;
+
+/// Carries the room's queue so routing a message does not need to lock `Rooms`.
+#[derive(Clone)]
+pub struct RoomEntry {
+    pub room_id: RoomUuid,
+    pub transmitter: Sender<ForwardedMessage>,
+}
+
+pub fn list_public_rooms(rooms: &Rooms) -> Vec<RoomSummary> {
+    rooms
+        .read()
+        .values()
+        .filter(|room_handle| room_handle.is_listed())
+        .map(RoomHandle::summary)
+        .collect()
+}
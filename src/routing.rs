@@ -0,0 +1,395 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use dashmap::{mapref::entry::Entry, DashMap};
+use tokio::sync::mpsc::{error::TrySendError, Sender};
+
+use crate::{
+    constants::{CONTROL_MESSAGE_RESERVE, OVERFLOW_CAPACITY_PER_PLAYER},
+    game::player::PlayerUuid,
+    message::{CurverMessageToReceive, CurverMessageToSend, ForwardedMessage},
+    metrics::metrics,
+    room_handle::RoomDirectory,
+};
+
+/// Connections that took over another player after a reconnect, with the player they speak
+/// for. Only the server handler adds to it.
+pub type ConnectionAliases = Arc<DashMap<PlayerUuid, PlayerUuid>>;
+
+/// Latest direction of every player in a room, taken by the room on its next tick.
+/// A newer rotation replaces an older one that was not used yet.
+pub type LatestRotations = Arc<DashMap<PlayerUuid, (f32, f32)>>;
+
+/// Entry point for messages of every connection. Rotations go into `LatestRotations`, other
+/// room bound messages go straight to the player's room, everything else is handled by the
+/// server handler. Room bound messages can overtake ones still waiting in the server
+/// handler's queue, so clients should wait for the answer to, for example, `addBot` before
+/// relying on it.
+#[derive(Clone)]
+pub struct Router {
+    server_transmitter: Sender<ForwardedMessage>,
+    room_directory: RoomDirectory,
+    connection_aliases: ConnectionAliases,
+    latest_rotations: LatestRotations,
+    overflow: Overflow,
+}
+
+impl Router {
+    pub fn new(
+        server_transmitter: Sender<ForwardedMessage>,
+        room_directory: RoomDirectory,
+        connection_aliases: ConnectionAliases,
+        latest_rotations: LatestRotations,
+    ) -> Router {
+        Router {
+            server_transmitter,
+            room_directory,
+            connection_aliases,
+            latest_rotations,
+            overflow: Overflow::default(),
+        }
+    }
+
+    pub fn forward(&self, mut forwarded_message: ForwardedMessage) {
+        let connection_id = forwarded_message.user_id;
+
+        if let Some(player_id) = self.connection_aliases.get(&connection_id) {
+            forwarded_message.user_id = *player_id;
+        }
+
+        if let CurverMessageToReceive::Disconnect = forwarded_message.message {
+            self.connection_aliases.remove(&connection_id);
+            self.latest_rotations.remove(&forwarded_message.user_id);
+        }
+
+        if !forwarded_message.message.is_room_bound() {
+            route(&self.server_transmitter, &self.overflow, forwarded_message);
+            return;
+        }
+
+        metrics()
+            .messages_received
+            .with_label_values(&[forwarded_message.message.message_type()])
+            .inc();
+
+        if let CurverMessageToReceive::Rotate {
+            angle_unit_vector_x,
+            angle_unit_vector_y,
+        } = forwarded_message.message
+        {
+            if self.room_directory.contains_key(&forwarded_message.user_id) {
+                self.latest_rotations.insert(
+                    forwarded_message.user_id,
+                    (angle_unit_vector_x, angle_unit_vector_y),
+                );
+            }
+
+            return;
+        }
+
+        let Some(transmitter) = self
+            .room_directory
+            .get(&forwarded_message.user_id)
+            .map(|room_entry| room_entry.transmitter.clone())
+        else {
+            tracing::debug!(player_id = %forwarded_message.user_id, "User is not in a room");
+            return;
+        };
+
+        route(&transmitter, &self.overflow, forwarded_message);
+    }
+}
+
+/// Forwards a message to the server handler or a room without blocking or panicking.
+///
+/// Ordinary messages only use the queue while it has more than `CONTROL_MESSAGE_RESERVE`
+/// free slots and are rejected with `ServerOverloaded` otherwise, so the client can retry.
+/// Lifecycle messages are always delivered: if the queue is full they wait in `overflow`,
+/// in the order they were routed.
+pub fn route(
+    transmitter: &Sender<ForwardedMessage>,
+    overflow: &Overflow,
+    forwarded_message: ForwardedMessage,
+) {
+    let message_type = forwarded_message.message.message_type();
+
+    if !forwarded_message.message.is_lifecycle() {
+        if transmitter.capacity() <= CONTROL_MESSAGE_RESERVE {
+            reject(forwarded_message);
+            return;
+        }
+
+        match transmitter.try_send(forwarded_message) {
+            Ok(()) => {}
+            Err(TrySendError::Full(forwarded_message)) => reject(forwarded_message),
+            Err(TrySendError::Closed(_)) => {
+                tracing::debug!(message_type, "Queue is closed, dropping message");
+            }
+        }
+
+        return;
+    }
+
+    match overflow.pending.entry(forwarded_message.user_id) {
+        // Lifecycle messages of a player never overtake each other
+        Entry::Occupied(mut waiting) => {
+            let is_disconnect = matches!(
+                forwarded_message.message,
+                CurverMessageToReceive::Disconnect
+            );
+
+            if waiting.get().len() >= OVERFLOW_CAPACITY_PER_PLAYER && !is_disconnect {
+                drop(waiting);
+                reject(forwarded_message);
+                return;
+            }
+
+            waiting
+                .get_mut()
+                .push_back((transmitter.clone(), forwarded_message));
+        }
+
+        Entry::Vacant(waiting) => match transmitter.try_send(forwarded_message) {
+            Ok(()) => {}
+
+            Err(TrySendError::Full(forwarded_message)) => {
+                tracing::warn!(message_type, "Queue is full, waiting to deliver");
+                let user_id = forwarded_message.user_id;
+
+                waiting.insert(VecDeque::from([(transmitter.clone(), forwarded_message)]));
+                tokio::spawn(deliver_overflow(overflow.pending.clone(), user_id));
+            }
+
+            // The receiving task is gone, so is everything the message would have changed
+            Err(TrySendError::Closed(_)) => {
+                tracing::debug!(message_type, "Queue is closed, dropping message");
+            }
+        },
+    }
+}
+
+fn reject(forwarded_message: ForwardedMessage) {
+    let message_type = forwarded_message.message.message_type();
+
+    tracing::warn!(message_type, "Queue is full, rejecting message");
+    metrics()
+        .messages_dropped
+        .with_label_values(&[message_type])
+        .inc();
+
+    forwarded_message
+        .address
+        .do_send(CurverMessageToSend::ServerOverloaded {
+            message_type: message_type.to_string(),
+        });
+}
+
+type PendingMessages = DashMap<PlayerUuid, VecDeque<(Sender<ForwardedMessage>, ForwardedMessage)>>;
+
+/// Lifecycle messages waiting for a full queue, per player. A single task per player with
+/// waiting messages delivers them, so their number stays bounded.
+#[derive(Clone, Default)]
+pub struct Overflow {
+    pending: Arc<PendingMessages>,
+}
+
+async fn deliver_overflow(pending: Arc<PendingMessages>, user_id: PlayerUuid) {
+    loop {
+        let next = pending
+            .get_mut(&user_id)
+            .and_then(|mut waiting| waiting.pop_front());
+
+        match next {
+            Some((transmitter, forwarded_message)) => {
+                let _ = transmitter.send(forwarded_message).await;
+            }
+
+            // Messages routed from now on go straight to the queue again
+            None => {
+                if pending
+                    .remove_if(&user_id, |_, waiting| waiting.is_empty())
+                    .is_some()
+                    || !pending.contains_key(&user_id)
+                {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::{self, Receiver};
+
+    use crate::client_sink::{CurverAddress, InMemoryClientSink, Outgoing};
+
+    use super::*;
+
+    struct Client {
+        user_id: PlayerUuid,
+        address: CurverAddress,
+        outgoing: Receiver<Outgoing>,
+    }
+
+    impl Client {
+        fn new() -> Client {
+            let (sink, outgoing) = InMemoryClientSink::new(16);
+
+            Client {
+                user_id: PlayerUuid::new(),
+                address: Arc::new(sink),
+                outgoing,
+            }
+        }
+
+        fn message(&self, message: CurverMessageToReceive) -> ForwardedMessage {
+            ForwardedMessage {
+                message,
+                user_id: self.user_id,
+                address: self.address.clone(),
+            }
+        }
+
+        fn rejected_message_types(&mut self) -> Vec<String> {
+            let mut message_types = Vec::new();
+
+            while let Ok(outgoing) = self.outgoing.try_recv() {
+                for message in outgoing.messages() {
+                    if let CurverMessageToSend::ServerOverloaded { message_type } = message {
+                        message_types.push(message_type.clone());
+                    }
+                }
+            }
+
+            message_types
+        }
+    }
+
+    fn is_ready(is_ready: bool) -> CurverMessageToReceive {
+        CurverMessageToReceive::IsReady { is_ready }
+    }
+
+    async fn receive_all(
+        receiver: &mut Receiver<ForwardedMessage>,
+        count: usize,
+    ) -> Vec<CurverMessageToReceive> {
+        let mut messages = Vec::new();
+
+        for _ in 0..count {
+            let forwarded_message = receiver.recv().await.expect("queue is open");
+            messages.push(forwarded_message.message);
+        }
+
+        messages
+    }
+
+    #[tokio::test]
+    async fn ordinary_messages_leave_room_for_control_messages() {
+        let (transmitter, mut receiver) = mpsc::channel(CONTROL_MESSAGE_RESERVE + 1);
+        let overflow = Overflow::default();
+        let mut client = Client::new();
+        let chat = || CurverMessageToReceive::SendChat {
+            text: "hello".to_string(),
+        };
+
+        route(&transmitter, &overflow, client.message(chat()));
+        route(&transmitter, &overflow, client.message(chat()));
+        route(&transmitter, &overflow, client.message(is_ready(true)));
+
+        assert_eq!(client.rejected_message_types(), vec!["sendChat"]);
+        assert_eq!(
+            receive_all(&mut receiver, 2).await,
+            vec![chat(), is_ready(true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn lifecycle_messages_wait_for_a_full_queue_in_order() {
+        let (transmitter, mut receiver) = mpsc::channel(1);
+        let overflow = Overflow::default();
+        let mut client = Client::new();
+        let messages = || {
+            vec![
+                CurverMessageToReceive::Connect,
+                is_ready(true),
+                is_ready(false),
+                CurverMessageToReceive::LeaveRoom,
+                CurverMessageToReceive::Disconnect,
+            ]
+        };
+
+        for message in messages() {
+            route(&transmitter, &overflow, client.message(message));
+        }
+
+        assert_eq!(receive_all(&mut receiver, 5).await, messages());
+        assert!(client.rejected_message_types().is_empty());
+
+        // Once everything is delivered, messages go straight to the queue again
+        tokio::task::yield_now().await;
+        assert!(overflow.pending.is_empty());
+
+        route(&transmitter, &overflow, client.message(is_ready(true)));
+        assert!(overflow.pending.is_empty());
+        assert_eq!(receive_all(&mut receiver, 1).await, vec![is_ready(true)]);
+    }
+
+    #[tokio::test]
+    async fn waiting_lifecycle_messages_are_capped_except_for_disconnects() {
+        let (transmitter, mut receiver) = mpsc::channel(1);
+        let overflow = Overflow::default();
+        let mut client = Client::new();
+
+        route(&transmitter, &overflow, client.message(is_ready(true)));
+        for _ in 0..OVERFLOW_CAPACITY_PER_PLAYER {
+            route(&transmitter, &overflow, client.message(is_ready(true)));
+        }
+        route(&transmitter, &overflow, client.message(is_ready(false)));
+        route(
+            &transmitter,
+            &overflow,
+            client.message(CurverMessageToReceive::Disconnect),
+        );
+
+        assert_eq!(client.rejected_message_types(), vec!["isReady"]);
+
+        let messages = receive_all(&mut receiver, OVERFLOW_CAPACITY_PER_PLAYER + 2).await;
+        assert!(messages[..OVERFLOW_CAPACITY_PER_PLAYER + 1]
+            .iter()
+            .all(|message| *message == is_ready(true)));
+        assert_eq!(messages.last(), Some(&CurverMessageToReceive::Disconnect));
+    }
+
+    #[tokio::test]
+    async fn players_do_not_wait_for_each_other() {
+        let (transmitter, mut receiver) = mpsc::channel(2);
+        let overflow = Overflow::default();
+        let waiting_client = Client::new();
+        let other_client = Client::new();
+
+        for message in [is_ready(true), is_ready(true), is_ready(false)] {
+            route(&transmitter, &overflow, waiting_client.message(message));
+        }
+
+        // The queue has room again before the waiting message is delivered
+        receiver.recv().await.expect("queue is open");
+        route(
+            &transmitter,
+            &overflow,
+            other_client.message(is_ready(true)),
+        );
+
+        let mut senders = Vec::new();
+        for _ in 0..3 {
+            senders.push(receiver.recv().await.expect("queue is open").user_id);
+        }
+        assert_eq!(
+            senders,
+            vec![
+                waiting_client.user_id,
+                other_client.user_id,
+                waiting_client.user_id
+            ]
+        );
+    }
+}
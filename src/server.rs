@@ -1,147 +1,917 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use dashmap::DashMap;
 use parking_lot::RwLock;
-use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        oneshot,
+    },
+    task::JoinError,
+};
+use tracing::Instrument;
 
 use crate::{
-    curver_error::ServerError,
-    curver_ws_actor::CurverAddress,
+    admin::{AdminCommand, PlayerInfo},
+    chat::{FileWordFilter, WordFilter},
+    client_sink::{broadcast, CurverAddress},
+    config::game_settings,
+    constants::{
+        DRAIN_CHECK_INTERVAL_MS, LOBBY_UPDATE_INTERVAL_MS, MATCHMAKING_INTERVAL_MS,
+        RATINGS_FILE_PATH, RECONNECT_AFTER_SECONDS, WORD_FILTER_FILE_PATH,
+    },
+    curver_error::{AdminError, HostActionError, JoinRoomError, ServerError},
     debug_ui::DebugUi,
-    game::player::PlayerUuid,
+    game::{bot::BotDifficulty, player::PlayerUuid, GameState},
+    matchmaking::MatchmakingQueue,
     message::{CurverMessageToReceive, CurverMessageToSend, ForwardedMessage},
+    metrics::metrics,
+    rating::PlayerRatings,
     room::{Room, RoomUuid},
+    room_access::{InviteToken, ReconnectToken, RoomAccess, RoomVisibility},
+    room_code::{RoomCode, RoomIdentifier},
+    room_handle::{list_public_rooms, RoomDirectory, RoomHandle, RoomSummary, Rooms},
+    routing::{route, ConnectionAliases, LatestRotations, Overflow},
+    worker_pool::WorkerPool,
 };
 
 pub struct ServerHandler {
-    room_message_transmitters: Arc<RwLock<HashMap<RoomUuid, Sender<ForwardedMessage>>>>,
-    room_map: HashMap<PlayerUuid, RoomUuid>,
+    connections: HashMap<PlayerUuid, CurverAddress>,
+    rooms: Rooms,
+    room_codes: Arc<RwLock<HashMap<RoomCode, RoomUuid>>>,
+    room_map: RoomDirectory,
+    connection_aliases: ConnectionAliases,
+    latest_rotations: LatestRotations,
+    /// Lifecycle messages for rooms whose queue was full.
+    room_overflow: Overflow,
+    matchmaking_queue: MatchmakingQueue,
+    ratings: Arc<RwLock<PlayerRatings>>,
+    word_filter: Arc<dyn WordFilter>,
+    worker_pool: WorkerPool,
+    lobby_subscribers: HashMap<PlayerUuid, CurverAddress>,
+    lobby_snapshot: HashMap<RoomUuid, RoomSummary>,
     internal_message_receiver: Receiver<ForwardedMessage>,
+    admin_command_transmitter: Sender<AdminCommand>,
+    admin_command_receiver: Receiver<AdminCommand>,
+    room_exit_transmitter: Sender<RoomExit>,
+    room_exit_receiver: Receiver<RoomExit>,
+    drain: Option<Drain>,
 
     debug_ui: DebugUi,
 }
 
+/// Sent by a room's supervisor when the room task stopped, with the panic message if it
+/// crashed.
+struct RoomExit {
+    room_id: RoomUuid,
+    panic: Option<String>,
+}
+
+/// While draining no new rooms are opened. Running games may finish until the deadline.
+struct Drain {
+    deadline: Instant,
+    /// Notified once every room is gone.
+    waiters: Vec<oneshot::Sender<()>>,
+}
+
 impl ServerHandler {
-    pub fn new(internal_message_receiver: Receiver<ForwardedMessage>) -> Self {
+    pub fn new(
+        internal_message_receiver: Receiver<ForwardedMessage>,
+        worker_pool: WorkerPool,
+    ) -> Self {
         let mut debug_ui = DebugUi::new();
         debug_ui.clear();
 
+        let (admin_command_transmitter, admin_command_receiver) = mpsc::channel(16);
+        let (room_exit_transmitter, room_exit_receiver) = mpsc::channel(16);
+
         Self {
-            room_message_transmitters: Arc::new(RwLock::new(HashMap::new())),
-            room_map: HashMap::new(),
+            connections: HashMap::new(),
+            rooms: Arc::new(RwLock::new(HashMap::new())),
+            room_codes: Arc::new(RwLock::new(HashMap::new())),
+            room_map: Arc::new(DashMap::new()),
+            connection_aliases: Arc::new(DashMap::new()),
+            latest_rotations: Arc::new(DashMap::new()),
+            room_overflow: Overflow::default(),
+            matchmaking_queue: MatchmakingQueue::new(),
+            ratings: Arc::new(RwLock::new(PlayerRatings::load(RATINGS_FILE_PATH))),
+            word_filter: Arc::new(FileWordFilter::load(WORD_FILTER_FILE_PATH)),
+            worker_pool,
+            lobby_subscribers: HashMap::new(),
+            lobby_snapshot: HashMap::new(),
             internal_message_receiver,
+            admin_command_transmitter,
+            admin_command_receiver,
+            room_exit_transmitter,
+            room_exit_receiver,
+            drain: None,
             debug_ui,
         }
     }
 
+    /// Shared with the HTTP lobby endpoint.
+    pub fn rooms(&self) -> Rooms {
+        self.rooms.clone()
+    }
+
+    /// Lets connections find the room of a player without going through the server handler.
+    pub fn room_directory(&self) -> RoomDirectory {
+        self.room_map.clone()
+    }
+
+    /// Lets connections speak for the player they took over.
+    pub fn connection_aliases(&self) -> ConnectionAliases {
+        self.connection_aliases.clone()
+    }
+
+    /// Lets connections steer without queueing a message for every rotation.
+    pub fn latest_rotations(&self) -> LatestRotations {
+        self.latest_rotations.clone()
+    }
+
+    /// Used by the admin API.
+    pub fn admin_commands(&self) -> Sender<AdminCommand> {
+        self.admin_command_transmitter.clone()
+    }
+
     /// This thread will always be running.
     pub async fn message_handler(mut self) {
+        let mut matchmaking_interval =
+            tokio::time::interval(Duration::from_millis(MATCHMAKING_INTERVAL_MS));
+        let mut lobby_interval =
+            tokio::time::interval(Duration::from_millis(LOBBY_UPDATE_INTERVAL_MS));
+        let mut drain_interval =
+            tokio::time::interval(Duration::from_millis(DRAIN_CHECK_INTERVAL_MS));
+
         loop {
-            if let Some(forwarded_message) = self.internal_message_receiver.recv().await {
-                match forwarded_message.message {
-                    CurverMessageToReceive::CreateRoom => {
-                        let room_id = self.create_room();
+            tokio::select! {
+                Some(forwarded_message) = self.internal_message_receiver.recv() => {
+                    self.handle_forwarded_message(forwarded_message);
+                }
+
+                Some(admin_command) = self.admin_command_receiver.recv() => {
+                    self.handle_admin_command(admin_command);
+                }
+
+                Some(room_exit) = self.room_exit_receiver.recv() => {
+                    self.remove_stopped_room(room_exit);
+                }
+
+                _ = matchmaking_interval.tick() => {
+                    self.match_queued_players();
+                }
+
+                _ = lobby_interval.tick() => {
+                    self.send_lobby_update_to_subscribers();
+                }
 
-                        self.join_room_and_forward_message(
-                            room_id,
-                            forwarded_message.user_id,
-                            forwarded_message.address.clone(),
-                        );
+                _ = drain_interval.tick() => {
+                    self.close_drained_rooms();
+                }
+            }
+        }
+    }
+
+    fn handle_forwarded_message(&mut self, forwarded_message: ForwardedMessage) {
+        metrics()
+            .messages_received
+            .with_label_values(&[forwarded_message.message.message_type()])
+            .inc();
 
+        match forwarded_message.message {
+            CurverMessageToReceive::CreateRoom {
+                visibility,
+                password,
+                max_players,
+            } => {
+                if self.refuse_while_draining(&forwarded_message.address) {
+                    return;
+                }
+
+                self.leave_current_room_and_notify(
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                );
+
+                let (room_id, room_code) = self.create_room(visibility, password, max_players);
+
+                self.join_room_and_notify(
+                    room_id,
+                    room_code,
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                );
+            }
+
+            CurverMessageToReceive::JoinRoom {
+                room_id,
+                password,
+                invite_token,
+            } => {
+                let (room_id, room_code) = match self.authorize_join(
+                    forwarded_message.user_id,
+                    room_id,
+                    password.as_deref(),
+                    invite_token.as_ref(),
+                ) {
+                    Ok(room) => room,
+                    Err(error) => {
                         forwarded_message
                             .address
-                            .do_send(CurverMessageToSend::JoinedRoom {
-                                room_id,
-                                user_id: forwarded_message.user_id,
+                            .do_send(CurverMessageToSend::JoinRoomError {
+                                reason: error.to_string(),
+                                code: error,
                             });
+                        return;
                     }
+                };
+
+                self.leave_current_room_and_notify(
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                );
 
-                    CurverMessageToReceive::JoinRoom { room_id } => {
-                        if !self.check_if_room_exists(room_id) {
-                            forwarded_message
-                                .address
-                                .do_send(CurverMessageToSend::JoinRoomError {
-                                    reason: ServerError::RoomDoesNotExist(room_id.get_uuid())
-                                        .to_string(),
-                                });
-                            continue;
-                        }
-
-                        self.join_room_and_forward_message(
-                            room_id,
-                            forwarded_message.user_id,
-                            forwarded_message.address.clone(),
-                        );
+                self.join_room_and_notify(
+                    room_id,
+                    room_code,
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                );
+            }
 
+            CurverMessageToReceive::RejoinRoom {
+                room_id,
+                reconnect_token,
+            } => {
+                let (room_code, player_id) = match self.authorize_rejoin(
+                    forwarded_message.user_id,
+                    room_id,
+                    &reconnect_token,
+                ) {
+                    Ok(seat) => seat,
+                    Err(error) => {
                         forwarded_message
                             .address
-                            .do_send(CurverMessageToSend::JoinedRoom {
-                                room_id,
-                                user_id: forwarded_message.user_id,
+                            .do_send(CurverMessageToSend::JoinRoomError {
+                                reason: error.to_string(),
+                                code: error,
                             });
+                        return;
                     }
+                };
 
-                    CurverMessageToReceive::LeaveRoom => {
-                        self.leave_room_and_forward_message(
-                            forwarded_message.user_id,
-                            forwarded_message.address.clone(),
-                        );
+                self.leave_current_room_and_notify(
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                );
 
-                        forwarded_message
-                            .address
-                            .do_send(CurverMessageToSend::LeftRoom);
-                    }
+                self.rejoin_room_and_notify(
+                    room_id,
+                    room_code,
+                    forwarded_message.user_id,
+                    player_id,
+                    forwarded_message.address.clone(),
+                );
+            }
 
-                    CurverMessageToReceive::Rotate {
-                        angle_unit_vector_x,
-                        angle_unit_vector_y,
-                    } => {
-                        self.send_message_to_room_by_user_id(
-                            forwarded_message.user_id,
-                            ForwardedMessage {
-                                user_id: forwarded_message.user_id,
-                                address: forwarded_message.address.clone(),
-                                message: CurverMessageToReceive::Rotate {
-                                    angle_unit_vector_x,
-                                    angle_unit_vector_y,
-                                },
-                            },
-                        );
-                    }
+            // Only the server sends these, to a room
+            message @ (CurverMessageToReceive::ReclaimSeat
+            | CurverMessageToReceive::ForfeitSeat) => {
+                tracing::warn!(
+                    player_id = %forwarded_message.user_id,
+                    message_type = message.message_type(),
+                    "Message meant for a room reached the server handler, dropping it"
+                );
+            }
 
-                    CurverMessageToReceive::IsReady { is_ready } => {
-                        self.send_message_to_room_by_user_id(
-                            forwarded_message.user_id,
-                            ForwardedMessage {
-                                user_id: forwarded_message.user_id,
-                                address: forwarded_message.address.clone(),
-                                message: CurverMessageToReceive::IsReady { is_ready },
-                            },
-                        );
-                    }
+            CurverMessageToReceive::FindMatch => {
+                if self.refuse_while_draining(&forwarded_message.address) {
+                    return;
+                }
+
+                self.leave_current_room_and_notify(
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                );
+
+                let rating = self.ratings.read().rating_of(forwarded_message.user_id);
+
+                self.matchmaking_queue.enqueue(
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                    rating,
+                );
+                self.matchmaking_queue.send_status_to_all();
+            }
+
+            CurverMessageToReceive::CancelMatchmaking => {
+                self.matchmaking_queue.remove(forwarded_message.user_id);
+
+                forwarded_message
+                    .address
+                    .do_send(CurverMessageToSend::MatchmakingCancelled);
+            }
+
+            CurverMessageToReceive::SetIdentity { identity } => {
+                self.ratings
+                    .write()
+                    .set_identity(forwarded_message.user_id, identity);
+            }
+
+            CurverMessageToReceive::SubscribeLobby => {
+                // Existing subscribers catch up first, so everyone shares the same snapshot
+                self.send_lobby_update_to_subscribers();
+
+                let rooms = list_public_rooms(&self.rooms);
+                self.lobby_snapshot = rooms
+                    .iter()
+                    .map(|summary| (summary.room_id, summary.clone()))
+                    .collect();
+
+                self.lobby_subscribers
+                    .insert(forwarded_message.user_id, forwarded_message.address.clone());
+
+                forwarded_message
+                    .address
+                    .do_send(CurverMessageToSend::LobbySnapshot { rooms });
+            }
+
+            CurverMessageToReceive::UnsubscribeLobby => {
+                self.lobby_subscribers.remove(&forwarded_message.user_id);
+            }
+
+            CurverMessageToReceive::Connect => {
+                if let Some(drain) = &self.drain {
+                    forwarded_message.address.do_send(drain.shutting_down());
                 }
+
+                self.connections
+                    .insert(forwarded_message.user_id, forwarded_message.address);
+            }
+
+            CurverMessageToReceive::Disconnect => {
+                self.connections.remove(&forwarded_message.user_id);
+                self.matchmaking_queue.remove(forwarded_message.user_id);
+                self.lobby_subscribers.remove(&forwarded_message.user_id);
+                self.ratings
+                    .write()
+                    .remove_identity(forwarded_message.user_id);
+
+                self.leave_room_and_forward_message(
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                );
+            }
+
+            CurverMessageToReceive::LeaveRoom => {
+                self.matchmaking_queue.remove(forwarded_message.user_id);
+
+                self.leave_room_and_forward_message(
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                );
+
+                forwarded_message
+                    .address
+                    .do_send(CurverMessageToSend::LeftRoom);
+            }
+
+            CurverMessageToReceive::CreateInvite => {
+                let message = match self.create_invite(forwarded_message.user_id) {
+                    Ok((room_id, room_code, invite_token)) => CurverMessageToSend::InviteCreated {
+                        room_id,
+                        room_code,
+                        invite_token,
+                    },
+                    Err(reason) => CurverMessageToSend::InviteError { reason },
+                };
+
+                forwarded_message.address.do_send(message);
+            }
+
+            CurverMessageToReceive::KickPlayer { user_id } => {
+                let result = self.kick_player(forwarded_message.user_id, user_id);
+                self.send_host_action_result(&forwarded_message.address, result);
+            }
+
+            CurverMessageToReceive::MutePlayer { user_id, muted } => {
+                let result = self.mute_player(
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                    user_id,
+                    muted,
+                );
+                self.send_host_action_result(&forwarded_message.address, result);
+            }
+
+            CurverMessageToReceive::AddBot { difficulty } => {
+                let result = self.add_bot(
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                    difficulty,
+                );
+                self.send_host_action_result(&forwarded_message.address, result);
+            }
+
+            CurverMessageToReceive::RemoveBot { user_id } => {
+                let result = self.remove_bot(
+                    forwarded_message.user_id,
+                    forwarded_message.address.clone(),
+                    user_id,
+                );
+                self.send_host_action_result(&forwarded_message.address, result);
+            }
+
+            CurverMessageToReceive::LockRoom { locked } => {
+                let result = self.lock_room(forwarded_message.user_id, locked);
+                self.send_host_action_result(&forwarded_message.address, result);
+            }
+
+            CurverMessageToReceive::UpdateRoomSettings {
+                max_players,
+                visibility,
+                password,
+            } => {
+                let result = self.update_room_settings(
+                    forwarded_message.user_id,
+                    max_players,
+                    visibility,
+                    password,
+                );
+                self.send_host_action_result(&forwarded_message.address, result);
+            }
+
+            CurverMessageToReceive::ForceStart => {
+                let result =
+                    self.force_start(forwarded_message.user_id, forwarded_message.address.clone());
+                self.send_host_action_result(&forwarded_message.address, result);
+            }
+
+            // Connections send these to the room directly
+            message @ (CurverMessageToReceive::Rotate { .. }
+            | CurverMessageToReceive::SetNickname { .. }
+            | CurverMessageToReceive::SendChat { .. }
+            | CurverMessageToReceive::IsReady { .. }) => {
+                tracing::warn!(
+                    message_type = message.message_type(),
+                    "Room bound message reached the server handler, dropping it"
+                );
             }
         }
     }
 
+    // --- Admin ---
+    fn handle_admin_command(&mut self, admin_command: AdminCommand) {
+        match admin_command {
+            AdminCommand::ListPlayers { respond } => {
+                let _ = respond.send(self.list_players());
+            }
+
+            AdminCommand::KickPlayer { user_id, respond } => {
+                let _ = respond.send(self.kick_player_from_room(user_id));
+            }
+
+            AdminCommand::CloseRoom {
+                room_id,
+                reason,
+                respond,
+            } => {
+                let _ = respond.send(self.close_room(room_id, reason));
+            }
+
+            AdminCommand::Drain { timeout, respond } => {
+                self.start_drain(timeout, respond);
+            }
+
+            AdminCommand::BroadcastNotice { text, respond } => {
+                tracing::info!(%text, "Broadcasting server notice");
+
+                broadcast(
+                    self.connections.values(),
+                    vec![CurverMessageToSend::ServerNotice { text }],
+                );
+
+                let _ = respond.send(self.connections.len());
+            }
+        }
+    }
+
+    fn list_players(&self) -> Vec<PlayerInfo> {
+        self.connections
+            .keys()
+            .map(|user_id| PlayerInfo {
+                user_id: *user_id,
+                room_id: self.room_of(*user_id),
+                in_matchmaking: self.matchmaking_queue.contains(*user_id),
+                subscribed_to_lobby: self.lobby_subscribers.contains_key(user_id),
+            })
+            .collect()
+    }
+
+    fn kick_player_from_room(&mut self, user_id: PlayerUuid) -> Result<(), AdminError> {
+        let address = self
+            .connections
+            .get(&user_id)
+            .cloned()
+            .ok_or(AdminError::PlayerNotFound)?;
+
+        if !self.room_map.contains_key(&user_id) {
+            return Err(AdminError::PlayerNotFound);
+        }
+
+        tracing::info!(player_id = %user_id, "Kicking player by operator");
+
+        self.kick_from_room_and_notify(user_id, address);
+
+        Ok(())
+    }
+
+    /// Everyone leaves the room, which makes the room task stop.
+    fn close_room(&mut self, room_id: RoomUuid, reason: String) -> Result<(), AdminError> {
+        let members: Vec<(PlayerUuid, CurverAddress)> = {
+            let rooms_lock = self.rooms.read();
+            let room_handle = rooms_lock.get(&room_id).ok_or(AdminError::RoomNotFound)?;
+            room_handle.close();
+
+            room_handle
+                .members
+                .iter()
+                .map(|(user_id, address)| (*user_id, address.clone()))
+                .collect()
+        };
+
+        tracing::info!(room_id = %room_id, %reason, "Closing room");
+
+        for (user_id, address) in members {
+            self.leave_room_and_forward_message(user_id, address.clone());
+            address.do_send(CurverMessageToSend::RoomClosed {
+                reason: reason.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    // --- Drain ---
+    fn start_drain(&mut self, timeout: Duration, respond: oneshot::Sender<()>) {
+        let drain = self.drain.get_or_insert_with(|| {
+            tracing::info!(timeout_seconds = timeout.as_secs(), "Draining server");
+
+            Drain {
+                deadline: Instant::now() + timeout,
+                waiters: Vec::new(),
+            }
+        });
+        drain.waiters.push(respond);
+
+        let shutting_down = drain.shutting_down();
+        broadcast(self.connections.values(), vec![shutting_down]);
+
+        for queued_player in self.matchmaking_queue.take_all() {
+            queued_player
+                .address
+                .do_send(CurverMessageToSend::MatchmakingCancelled);
+        }
+
+        self.close_drained_rooms();
+    }
+
+    fn refuse_while_draining(&self, address: &CurverAddress) -> bool {
+        if self.drain.is_none() {
+            return false;
+        }
+
+        let error = JoinRoomError::ServerShuttingDown;
+        address.do_send(CurverMessageToSend::JoinRoomError {
+            reason: error.to_string(),
+            code: error,
+        });
+
+        true
+    }
+
+    /// Closes rooms that are not playing, and every room once the deadline has passed.
+    fn close_drained_rooms(&mut self) {
+        let Some(drain) = &self.drain else {
+            return;
+        };
+
+        let deadline_passed = Instant::now() >= drain.deadline;
+        let room_ids: Vec<RoomUuid> = self
+            .rooms
+            .read()
+            .values()
+            .filter(|room_handle| deadline_passed || room_handle.game_state() == GameState::Waiting)
+            .map(|room_handle| room_handle.id)
+            .collect();
+
+        for room_id in room_ids {
+            let _ = self.close_room(room_id, "Server is shutting down".to_string());
+        }
+
+        // Stopped rooms are removed from `rooms` once their supervisor reported it
+        if self.rooms.read().is_empty() {
+            if let Some(drain) = self.drain.as_mut() {
+                for waiter in drain.waiters.drain(..) {
+                    let _ = waiter.send(());
+                }
+            }
+        }
+    }
+
+    /// A room can stop while someone is still joining it, so remaining members are told the
+    /// room is gone and are free to join another one.
+    fn remove_stopped_room(&mut self, room_exit: RoomExit) {
+        let Some(room_handle) = self.rooms.write().remove(&room_exit.room_id) else {
+            return;
+        };
+        self.room_codes.write().remove(&room_handle.code);
+
+        let reason = match &room_exit.panic {
+            Some(panic) => {
+                tracing::warn!(
+                    room_id = %room_exit.room_id,
+                    reason = %panic,
+                    member_count = room_handle.members.len(),
+                    "Removing crashed room"
+                );
+
+                "Room stopped after an internal error"
+            }
+            None => "Room closed",
+        };
+
+        for (user_id, address) in room_handle.members {
+            self.room_map.remove(&user_id);
+            address.do_send(CurverMessageToSend::RoomClosed {
+                reason: reason.to_string(),
+            });
+        }
+
+        self.debug_assert_membership_consistent();
+        self.draw_rooms_in_debug_ui();
+    }
+
     // --- Room Handling ---
-    fn create_room(&mut self) -> RoomUuid {
+    fn create_room(
+        &mut self,
+        visibility: RoomVisibility,
+        password: Option<String>,
+        max_players: Option<usize>,
+    ) -> (RoomUuid, RoomCode) {
         let room_id = RoomUuid::new();
+        let room_code = self.generate_room_code();
         let (room_message_transmitter, room_message_receiver) = mpsc::channel(100);
-        let room_message_transmitters_clone = self.room_message_transmitters.clone();
 
-        let room = Room::new(room_message_receiver);
+        let worker = self.worker_pool.least_loaded();
+        let room = Room::new(
+            room_message_receiver,
+            self.ratings.clone(),
+            self.word_filter.clone(),
+            worker.game_scheduler(),
+            self.latest_rotations.clone(),
+        );
+        let snapshot = room.snapshot();
+        let closed = room.closed();
 
-        tokio::spawn(async move {
-            room.message_handler().await;
+        let span = tracing::info_span!("room", room_id = %room_id, room_code = %room_code);
+        let room_task = worker.spawn_room(room.message_handler().instrument(span.clone()));
+        let room_exit_transmitter = self.room_exit_transmitter.clone();
 
-            print!("Room {} dropped", room_id);
-            let mut transmitter_lock = room_message_transmitters_clone.write();
-            transmitter_lock.remove(&room_id);
+        // Supervises the room task, a panic only takes down this room
+        tokio::spawn(
+            async move {
+                tracing::info!("Room created");
+
+                let panic = match room_task.await {
+                    Ok(()) => {
+                        tracing::info!("Room dropped");
+                        None
+                    }
+                    Err(error) => {
+                        let reason = panic_message(error);
+                        tracing::error!(%reason, "Room crashed");
+                        metrics().room_crashes.inc();
+                        Some(reason)
+                    }
+                };
+
+                let _ = room_exit_transmitter
+                    .send(RoomExit { room_id, panic })
+                    .await;
+            }
+            .instrument(span),
+        );
+
+        self.add_room(RoomHandle {
+            id: room_id,
+            transmitter: room_message_transmitter,
+            code: room_code.clone(),
+            access: RoomAccess::new(visibility, password),
+            max_players: max_players
+                .unwrap_or(game_settings().default_max_players)
+                .clamp(
+                    game_settings().min_players_to_start,
+                    game_settings().max_players_limit,
+                ),
+            locked: false,
+            host: None,
+            members: HashMap::new(),
+            bots: HashSet::new(),
+            snapshot,
+            closed,
+            reconnect_tokens: HashMap::new(),
+            revoked_reconnect_tokens: HashSet::new(),
         });
 
-        self.add_room_transmitter(room_id, room_message_transmitter);
+        (room_id, room_code)
+    }
+
+    fn generate_room_code(&self) -> RoomCode {
+        let room_codes_lock = self.room_codes.read();
+
+        loop {
+            let room_code = RoomCode::generate();
+
+            if !room_codes_lock.contains_key(&room_code) {
+                return room_code;
+            }
+        }
+    }
+
+    fn authorize_join(
+        &self,
+        user_id: PlayerUuid,
+        room_identifier: RoomIdentifier,
+        password: Option<&str>,
+        invite_token: Option<&InviteToken>,
+    ) -> Result<(RoomUuid, RoomCode), JoinRoomError> {
+        if self.drain.is_some() {
+            return Err(JoinRoomError::ServerShuttingDown);
+        }
+
+        let room_id = match room_identifier {
+            RoomIdentifier::Uuid(room_id) => room_id,
+            RoomIdentifier::Code(room_code) => *self
+                .room_codes
+                .read()
+                .get(&room_code)
+                .ok_or(JoinRoomError::RoomDoesNotExist)?,
+        };
+
+        let mut rooms_lock = self.rooms.write();
+        let room_handle = rooms_lock
+            .get_mut(&room_id)
+            .ok_or(JoinRoomError::RoomDoesNotExist)?;
 
-        room_id
+        if room_handle.members.contains_key(&user_id) {
+            return Err(JoinRoomError::AlreadyInRoom);
+        }
+
+        room_handle.check_if_joinable()?;
+        room_handle.access.authorize_join(password, invite_token)?;
+
+        Ok((room_id, room_handle.code.clone()))
+    }
+
+    fn create_invite(
+        &self,
+        user_id: PlayerUuid,
+    ) -> Result<(RoomUuid, RoomCode, InviteToken), String> {
+        let room_id = self
+            .room_of(user_id)
+            .ok_or_else(|| format!("User {} is not in a room", user_id))?;
+
+        let mut rooms_lock = self.rooms.write();
+        let room_handle = rooms_lock
+            .get_mut(&room_id)
+            .ok_or_else(|| ServerError::RoomDoesNotExist(room_id.get_uuid()).to_string())?;
+
+        Ok((
+            room_id,
+            room_handle.code.clone(),
+            room_handle.access.create_invite(),
+        ))
+    }
+
+    fn join_room_and_notify(
+        &mut self,
+        room_id: RoomUuid,
+        room_code: RoomCode,
+        user_id: PlayerUuid,
+        address: CurverAddress,
+    ) {
+        self.matchmaking_queue.remove(user_id);
+        self.join_room_and_forward_message(room_id, user_id, address.clone());
+
+        let Some(reconnect_token) = self
+            .rooms
+            .write()
+            .get_mut(&room_id)
+            .map(|room_handle| room_handle.reconnect_token(user_id))
+        else {
+            return;
+        };
+
+        address.do_send(CurverMessageToSend::JoinedRoom {
+            room_id,
+            room_code,
+            user_id,
+            reconnect_token,
+        });
+
+        self.send_room_info_to_all(room_id);
+    }
+
+    /// Only while the room holds the player's seat, and not while the player's old
+    /// connection is still around.
+    fn authorize_rejoin(
+        &self,
+        user_id: PlayerUuid,
+        room_id: RoomUuid,
+        reconnect_token: &ReconnectToken,
+    ) -> Result<(RoomCode, PlayerUuid), JoinRoomError> {
+        let rooms_lock = self.rooms.read();
+        let room_handle = rooms_lock
+            .get(&room_id)
+            .ok_or(JoinRoomError::RoomDoesNotExist)?;
+
+        if room_handle
+            .revoked_reconnect_tokens
+            .contains(reconnect_token)
+        {
+            return Err(JoinRoomError::SeatNotHeld);
+        }
+
+        let player_id = room_handle
+            .seat_of(reconnect_token)
+            .ok_or(JoinRoomError::InvalidReconnectToken)?;
+
+        let is_held = room_handle
+            .snapshot
+            .borrow()
+            .held_seats
+            .contains(&player_id);
+        let is_connected_elsewhere =
+            player_id != user_id && self.connections.contains_key(&player_id);
+
+        if !is_held || is_connected_elsewhere {
+            return Err(JoinRoomError::SeatNotHeld);
+        }
+
+        Ok((room_handle.code.clone(), player_id))
+    }
+
+    /// From now on the connection speaks for the player it took over.
+    fn rejoin_room_and_notify(
+        &mut self,
+        room_id: RoomUuid,
+        room_code: RoomCode,
+        user_id: PlayerUuid,
+        player_id: PlayerUuid,
+        address: CurverAddress,
+    ) {
+        let Some(reconnect_token) = self.rooms.write().get_mut(&room_id).map(|room_handle| {
+            room_handle.add_member(player_id, address.clone());
+            self.room_map.insert(player_id, room_handle.entry());
+            room_handle.reconnect_token(player_id)
+        }) else {
+            return;
+        };
+
+        if player_id != user_id {
+            self.matchmaking_queue.remove(user_id);
+            self.lobby_subscribers.remove(&user_id);
+            self.connections.remove(&user_id);
+            self.connections.insert(player_id, address.clone());
+            self.ratings.write().move_identity(user_id, player_id);
+            self.connection_aliases.insert(user_id, player_id);
+        }
+
+        self.debug_assert_membership_consistent();
+        self.draw_rooms_in_debug_ui();
+
+        self.send_message_to_room(
+            room_id,
+            ForwardedMessage {
+                user_id: player_id,
+                address: address.clone(),
+                message: CurverMessageToReceive::ReclaimSeat,
+            },
+        );
+
+        address.do_send(CurverMessageToSend::JoinedRoom {
+            room_id,
+            room_code,
+            user_id: player_id,
+            reconnect_token,
+        });
+
+        self.send_room_info_to_all(room_id);
     }
 
     fn join_room_and_forward_message(
@@ -150,59 +920,534 @@ impl ServerHandler {
         user_id: PlayerUuid,
         address: CurverAddress,
     ) {
-        self.room_map.insert(user_id, room_id);
-        self.debug_ui.draw_rooms(self.room_map.clone());
+        if let Some(room_handle) = self.rooms.write().get_mut(&room_id) {
+            room_handle.add_member(user_id, address.clone());
+            self.room_map.insert(user_id, room_handle.entry());
+        }
+        self.debug_assert_membership_consistent();
+        self.draw_rooms_in_debug_ui();
 
         self.send_message_to_room(
             room_id,
             ForwardedMessage {
                 user_id,
                 address,
-                message: CurverMessageToReceive::JoinRoom { room_id },
+                message: CurverMessageToReceive::JoinRoom {
+                    room_id: RoomIdentifier::Uuid(room_id),
+                    password: None,
+                    invite_token: None,
+                },
             },
         );
     }
 
     fn leave_room_and_forward_message(&mut self, user_id: PlayerUuid, address: CurverAddress) {
+        self.remove_from_room_and_forward_message(
+            user_id,
+            address,
+            CurverMessageToReceive::LeaveRoom,
+        );
+    }
+
+    /// Unlike someone who leaves, a kicked player can not take their seat in a running game
+    /// back.
+    fn kick_from_room_and_notify(&mut self, user_id: PlayerUuid, address: CurverAddress) {
+        if let Some(room_id) = self.room_of(user_id) {
+            if let Some(room_handle) = self.rooms.write().get_mut(&room_id) {
+                room_handle.revoke_reconnect_token(user_id);
+            }
+        }
+
+        self.remove_from_room_and_forward_message(
+            user_id,
+            address.clone(),
+            CurverMessageToReceive::ForfeitSeat,
+        );
+        address.do_send(CurverMessageToSend::Kicked);
+    }
+
+    fn remove_from_room_and_forward_message(
+        &mut self,
+        user_id: PlayerUuid,
+        address: CurverAddress,
+        message: CurverMessageToReceive,
+    ) {
         self.send_message_to_room_by_user_id(
             user_id,
             ForwardedMessage {
                 user_id,
                 address,
-                message: CurverMessageToReceive::LeaveRoom,
+                message,
             },
         );
 
-        self.room_map.remove(&user_id);
-        self.debug_ui.draw_rooms(self.room_map.clone());
+        if let Some((_, room_entry)) = self.room_map.remove(&user_id) {
+            if let Some(room_handle) = self.rooms.write().get_mut(&room_entry.room_id) {
+                let host = room_handle.host;
+                room_handle.remove_member(user_id);
+
+                if room_handle.host != host {
+                    room_handle.send_room_info_to_all();
+                }
+            }
+        }
+        self.debug_assert_membership_consistent();
+        self.draw_rooms_in_debug_ui();
     }
 
-    fn check_if_room_exists(&self, room_id: RoomUuid) -> bool {
-        self.room_message_transmitters.read().contains_key(&room_id)
+    /// A player can only be in one room at a time, so joining or creating another room
+    /// leaves the current one first.
+    fn leave_current_room_and_notify(&mut self, user_id: PlayerUuid, address: CurverAddress) {
+        if !self.room_map.contains_key(&user_id) {
+            return;
+        }
+
+        self.leave_room_and_forward_message(user_id, address.clone());
+        address.do_send(CurverMessageToSend::LeftRoom);
+    }
+
+    /// `room_map` and the member list of every room must always describe the same thing.
+    fn debug_assert_membership_consistent(&self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let rooms_lock = self.rooms.read();
+
+        for entry in self.room_map.iter() {
+            let (user_id, room_entry) = entry.pair();
+            let room_id = &room_entry.room_id;
+            debug_assert!(
+                rooms_lock
+                    .get(room_id)
+                    .is_some_and(|room_handle| room_handle.members.contains_key(user_id)),
+                "User {} is mapped to room {} but is not one of its members",
+                user_id,
+                room_id
+            );
+        }
+
+        for (room_id, room_handle) in rooms_lock.iter() {
+            for user_id in room_handle.members.keys() {
+                debug_assert_eq!(
+                    self.room_of(*user_id).as_ref(),
+                    Some(room_id),
+                    "User {} is a member of room {} but is not mapped to it",
+                    user_id,
+                    room_id
+                );
+            }
+        }
+    }
+
+    fn room_of(&self, user_id: PlayerUuid) -> Option<RoomUuid> {
+        self.room_map
+            .get(&user_id)
+            .map(|room_entry| room_entry.room_id)
+    }
+
+    fn draw_rooms_in_debug_ui(&mut self) {
+        let room_map = self
+            .room_map
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().room_id))
+            .collect();
+
+        self.debug_ui.draw_rooms(room_map);
     }
 
     //  --- Message Forwarding ---
     fn send_message_to_room_by_user_id(&mut self, user_id: PlayerUuid, message: ForwardedMessage) {
-        if let Some(room_id) = self.room_map.get(&user_id) {
-            self.send_message_to_room(*room_id, message);
+        if let Some(room_id) = self.room_of(user_id) {
+            self.send_message_to_room(room_id, message);
         } else {
-            println!("User {} is not in a room", user_id);
+            tracing::warn!(player_id = %user_id, "User is not in a room");
         }
     }
 
     fn send_message_to_room(&self, room_id: RoomUuid, message: ForwardedMessage) {
-        let transmitter_lock = self.room_message_transmitters.read();
+        let rooms_lock = self.rooms.read();
 
-        if let Some(transmitter) = transmitter_lock.get(&room_id) {
-            transmitter.try_send(message).unwrap();
+        if let Some(room_handle) = rooms_lock.get(&room_id) {
+            route(&room_handle.transmitter, &self.room_overflow, message);
         } else {
-            println!("Room {} does not exist", room_id);
+            tracing::warn!(room_id = %room_id, "Room does not exist");
+        }
+    }
+
+    // --- Matchmaking ---
+    fn match_queued_players(&mut self) {
+        if self.matchmaking_queue.is_empty() {
+            return;
+        }
+
+        while let Some(rating) = self.matchmaking_queue.front().map(|player| player.rating) {
+            let Some((room_id, room_code)) = self.find_open_room(rating) else {
+                break;
+            };
+
+            let Some(queued_player) = self.matchmaking_queue.pop() else {
+                break;
+            };
+
+            self.join_room_and_notify(
+                room_id,
+                room_code,
+                queued_player.user_id,
+                queued_player.address,
+            );
+        }
+
+        while let Some(group) = self.matchmaking_queue.take_group_for_new_room() {
+            let (room_id, room_code) = self.create_room(
+                RoomVisibility::Public,
+                None,
+                Some(game_settings().matchmaking_room_size),
+            );
+
+            for queued_player in group {
+                self.join_room_and_notify(
+                    room_id,
+                    room_code.clone(),
+                    queued_player.user_id,
+                    queued_player.address,
+                );
+            }
+        }
+
+        self.matchmaking_queue.send_status_to_all();
+    }
+
+    /// Picks the public room whose players' average rating is closest to `rating`.
+    fn find_open_room(&self, rating: f64) -> Option<(RoomUuid, RoomCode)> {
+        let ratings_lock = self.ratings.read();
+        let rating_distance = |room_handle: &RoomHandle| {
+            let rating_sum: f64 = room_handle
+                .members
+                .keys()
+                .map(|user_id| ratings_lock.rating_of(*user_id))
+                .sum();
+
+            (rating_sum / room_handle.members.len() as f64 - rating).abs()
+        };
+
+        self.rooms
+            .read()
+            .values()
+            .filter(|room_handle| {
+                room_handle.access.visibility == RoomVisibility::Public
+                    && room_handle.access.password.is_none()
+                    && !room_handle.members.is_empty()
+                    && room_handle.check_if_joinable().is_ok()
+            })
+            .min_by(|a, b| rating_distance(a).total_cmp(&rating_distance(b)))
+            .map(|room_handle| (room_handle.id, room_handle.code.clone()))
+    }
+
+    // --- Lobby ---
+    /// Sends only the rooms that changed since the last update. Without subscribers the
+    /// snapshot is left alone, the next subscriber replaces it.
+    fn send_lobby_update_to_subscribers(&mut self) {
+        if self.lobby_subscribers.is_empty() {
+            return;
+        }
+
+        let current: HashMap<RoomUuid, RoomSummary> = list_public_rooms(&self.rooms)
+            .into_iter()
+            .map(|summary| (summary.room_id, summary))
+            .collect();
+
+        let updated: Vec<RoomSummary> = current
+            .values()
+            .filter(|summary| self.lobby_snapshot.get(&summary.room_id) != Some(*summary))
+            .cloned()
+            .collect();
+
+        let removed: Vec<RoomUuid> = self
+            .lobby_snapshot
+            .keys()
+            .filter(|room_id| !current.contains_key(room_id))
+            .cloned()
+            .collect();
+
+        self.lobby_snapshot = current;
+
+        if updated.is_empty() && removed.is_empty() {
+            return;
+        }
+
+        let update = CurverMessageToSend::LobbyUpdate { updated, removed };
+
+        broadcast(self.lobby_subscribers.values(), vec![update]);
+    }
+
+    // --- Host Actions ---
+    fn authorize_host(&self, user_id: PlayerUuid) -> Result<RoomUuid, HostActionError> {
+        let room_id = self.room_of(user_id).ok_or(HostActionError::NotInRoom)?;
+
+        let rooms_lock = self.rooms.read();
+        let room_handle = rooms_lock.get(&room_id).ok_or(HostActionError::NotInRoom)?;
+
+        if room_handle.host != Some(user_id) {
+            return Err(HostActionError::NotHost);
+        }
+
+        Ok(room_id)
+    }
+
+    fn kick_player(
+        &mut self,
+        host_id: PlayerUuid,
+        user_id: PlayerUuid,
+    ) -> Result<(), HostActionError> {
+        let room_id = self.authorize_host(host_id)?;
+
+        if host_id == user_id {
+            return Err(HostActionError::CannotKickSelf);
+        }
+
+        let address = self
+            .rooms
+            .read()
+            .get(&room_id)
+            .and_then(|room_handle| room_handle.members.get(&user_id).cloned())
+            .ok_or(HostActionError::PlayerNotInRoom)?;
+
+        self.kick_from_room_and_notify(user_id, address);
+
+        Ok(())
+    }
+
+    fn mute_player(
+        &mut self,
+        host_id: PlayerUuid,
+        address: CurverAddress,
+        user_id: PlayerUuid,
+        muted: bool,
+    ) -> Result<(), HostActionError> {
+        let room_id = self.authorize_host(host_id)?;
+
+        if self.room_of(user_id) != Some(room_id) {
+            return Err(HostActionError::PlayerNotInRoom);
+        }
+
+        self.send_message_to_room(
+            room_id,
+            ForwardedMessage {
+                user_id: host_id,
+                address,
+                message: CurverMessageToReceive::MutePlayer { user_id, muted },
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Bots get their own id, which the room uses as the bot's player id.
+    fn add_bot(
+        &mut self,
+        host_id: PlayerUuid,
+        address: CurverAddress,
+        difficulty: BotDifficulty,
+    ) -> Result<(), HostActionError> {
+        let room_id = self.authorize_host(host_id)?;
+        let bot_id = PlayerUuid::new();
+
+        {
+            let mut rooms_lock = self.rooms.write();
+            let room_handle = rooms_lock
+                .get_mut(&room_id)
+                .ok_or(HostActionError::NotInRoom)?;
+
+            if room_handle.game_state() != GameState::Waiting {
+                return Err(HostActionError::GameInProgress);
+            }
+
+            if room_handle.player_count() >= room_handle.max_players {
+                return Err(HostActionError::RoomFull);
+            }
+
+            room_handle.bots.insert(bot_id);
+        }
+
+        self.send_message_to_room(
+            room_id,
+            ForwardedMessage {
+                user_id: bot_id,
+                address,
+                message: CurverMessageToReceive::AddBot { difficulty },
+            },
+        );
+
+        Ok(())
+    }
+
+    fn remove_bot(
+        &mut self,
+        host_id: PlayerUuid,
+        address: CurverAddress,
+        bot_id: PlayerUuid,
+    ) -> Result<(), HostActionError> {
+        let room_id = self.authorize_host(host_id)?;
+
+        {
+            let mut rooms_lock = self.rooms.write();
+            let room_handle = rooms_lock
+                .get_mut(&room_id)
+                .ok_or(HostActionError::NotInRoom)?;
+
+            if room_handle.game_state() != GameState::Waiting {
+                return Err(HostActionError::GameInProgress);
+            }
+
+            if !room_handle.bots.remove(&bot_id) {
+                return Err(HostActionError::BotNotFound);
+            }
         }
+
+        self.send_message_to_room(
+            room_id,
+            ForwardedMessage {
+                user_id: host_id,
+                address,
+                message: CurverMessageToReceive::RemoveBot { user_id: bot_id },
+            },
+        );
+
+        Ok(())
     }
 
-    // --- Message Transmitter ---
-    fn add_room_transmitter(&mut self, room_id: RoomUuid, transmitter: Sender<ForwardedMessage>) {
-        let mut transmitter_lock = self.room_message_transmitters.write();
-        transmitter_lock.insert(room_id, transmitter);
+    fn lock_room(&mut self, host_id: PlayerUuid, locked: bool) -> Result<(), HostActionError> {
+        let room_id = self.authorize_host(host_id)?;
+
+        if let Some(room_handle) = self.rooms.write().get_mut(&room_id) {
+            room_handle.locked = locked;
+            room_handle.send_room_info_to_all();
+        }
+
+        Ok(())
+    }
+
+    fn update_room_settings(
+        &mut self,
+        host_id: PlayerUuid,
+        max_players: Option<usize>,
+        visibility: Option<RoomVisibility>,
+        password: Option<String>,
+    ) -> Result<(), HostActionError> {
+        let room_id = self.authorize_host(host_id)?;
+
+        let mut rooms_lock = self.rooms.write();
+        let room_handle = rooms_lock
+            .get_mut(&room_id)
+            .ok_or(HostActionError::NotInRoom)?;
+
+        if let Some(max_players) = max_players {
+            if !(game_settings().min_players_to_start..=game_settings().max_players_limit)
+                .contains(&max_players)
+                || max_players < room_handle.player_count()
+            {
+                return Err(HostActionError::InvalidSettings);
+            }
+
+            room_handle.max_players = max_players;
+        }
+
+        if let Some(visibility) = visibility {
+            room_handle.access.visibility = visibility;
+        }
+
+        if let Some(password) = password {
+            room_handle.access.password = Some(password).filter(|password| !password.is_empty());
+        }
+
+        room_handle.send_room_info_to_all();
+
+        Ok(())
+    }
+
+    fn force_start(
+        &mut self,
+        host_id: PlayerUuid,
+        address: CurverAddress,
+    ) -> Result<(), HostActionError> {
+        let room_id = self.authorize_host(host_id)?;
+
+        {
+            let rooms_lock = self.rooms.read();
+            let room_handle = rooms_lock.get(&room_id).ok_or(HostActionError::NotInRoom)?;
+
+            if room_handle.game_state() != GameState::Waiting {
+                return Err(HostActionError::GameInProgress);
+            }
+
+            if room_handle.player_count() < game_settings().min_players_to_start {
+                return Err(HostActionError::NotEnoughPlayers);
+            }
+        }
+
+        self.send_message_to_room(
+            room_id,
+            ForwardedMessage {
+                user_id: host_id,
+                address,
+                message: CurverMessageToReceive::ForceStart,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn send_room_info_to_all(&self, room_id: RoomUuid) {
+        if let Some(room_handle) = self.rooms.read().get(&room_id) {
+            room_handle.send_room_info_to_all();
+        }
+    }
+
+    fn send_host_action_result(
+        &self,
+        address: &CurverAddress,
+        result: Result<(), HostActionError>,
+    ) {
+        if let Err(error) = result {
+            address.do_send(CurverMessageToSend::HostActionError {
+                reason: error.to_string(),
+                code: error,
+            });
+        }
+    }
+
+    // --- Room Registry ---
+    fn add_room(&mut self, room_handle: RoomHandle) {
+        self.room_codes
+            .write()
+            .insert(room_handle.code.clone(), room_handle.id);
+        self.rooms.write().insert(room_handle.id, room_handle);
+    }
+}
+
+fn panic_message(error: JoinError) -> String {
+    if !error.is_panic() {
+        return error.to_string();
+    }
+
+    let payload = error.into_panic();
+
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Unknown panic".to_string())
+}
+
+impl Drain {
+    fn shutting_down(&self) -> CurverMessageToSend {
+        CurverMessageToSend::ShuttingDown {
+            deadline_seconds: self
+                .deadline
+                .saturating_duration_since(Instant::now())
+                .as_secs(),
+            reconnect_after_seconds: RECONNECT_AFTER_SECONDS,
+        }
     }
 }
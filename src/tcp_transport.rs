@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use bytestring::ByteString;
+use futures_util::StreamExt;
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+};
+use tokio_util::codec::{FramedRead, LinesCodec, LinesCodecError};
+use tracing::Instrument;
+
+use crate::{
+    client_sink::{CurverAddress, InMemoryClientSink, Outgoing},
+    constants::{CLIENT_OUTGOING_QUEUE_CAPACITY, TCP_MAX_LINE_LENGTH},
+    game::player::PlayerUuid,
+    message::{CurverMessageToReceive, CurverMessageToSend, ForwardedMessage},
+    metrics::metrics,
+    routing::Router,
+};
+
+/// Accepts raw TCP clients speaking newline delimited JSON, one message per line,
+/// with the same messages as the WebSocket. Clients sending overly long lines or not reading
+/// their messages fast enough are disconnected.
+pub async fn listen(address: impl ToSocketAddrs, router: Router) -> std::io::Result<()> {
+    let listener = TcpListener::bind(address).await?;
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let router = router.clone();
+
+        let id = PlayerUuid::new();
+        let span = tracing::info_span!("connection", player_id = %id, transport = "tcp");
+
+        tokio::spawn(
+            async move {
+                handle_connection(id, stream, router).await;
+            }
+            .instrument(span),
+        );
+    }
+}
+
+async fn handle_connection(id: PlayerUuid, stream: TcpStream, router: Router) {
+    tracing::info!("Connected");
+    metrics().active_connections.inc();
+    let (reader, mut writer) = stream.into_split();
+
+    let (sink, mut outgoing) = InMemoryClientSink::new(CLIENT_OUTGOING_QUEUE_CAPACITY);
+    let lagging = sink.lagging();
+    let address: CurverAddress = Arc::new(sink);
+
+    let writer = tokio::spawn(async move {
+        while let Some(outgoing) = outgoing.recv().await {
+            for message in outgoing.messages() {
+                metrics()
+                    .messages_sent
+                    .with_label_values(&[message.message_type()])
+                    .inc();
+            }
+
+            let line = match &outgoing {
+                Outgoing::Message(message) => match serde_json::to_string(message) {
+                    Ok(line) => ByteString::from(line),
+                    Err(_) => continue,
+                },
+                Outgoing::Frame(frame) => frame.text().clone(),
+            };
+
+            if writer.write_all(line.as_bytes()).await.is_err()
+                || writer.write_all(b"\n").await.is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    router.forward(ForwardedMessage {
+        user_id: id,
+        address: address.clone(),
+        message: CurverMessageToReceive::Connect,
+    });
+
+    let mut lines = FramedRead::new(reader, LinesCodec::new_with_max_length(TCP_MAX_LINE_LENGTH));
+
+    loop {
+        let line = tokio::select! {
+            line = lines.next() => line,
+            _ = lagging.notified() => {
+                tracing::warn!("Client is not reading its messages, disconnecting");
+                break;
+            }
+        };
+
+        let line = match line {
+            Some(Ok(line)) => line,
+            Some(Err(LinesCodecError::MaxLineLengthExceeded)) => {
+                tracing::warn!(
+                    max_length = TCP_MAX_LINE_LENGTH,
+                    "Client sent an overly long line, disconnecting"
+                );
+                break;
+            }
+            Some(Err(LinesCodecError::Io(_))) | None => break,
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<CurverMessageToReceive>(&line) {
+            Ok(message) => {
+                router.forward(ForwardedMessage {
+                    message,
+                    user_id: id,
+                    address: address.clone(),
+                });
+            }
+            Err(_) => {
+                tracing::debug!(message = %line, "Faulty message");
+                metrics().faulty_messages.inc();
+                address.do_send(CurverMessageToSend::FaultyMessage { message: line });
+            }
+        }
+    }
+
+    writer.abort();
+
+    tracing::info!("Disconnected");
+    metrics().active_connections.dec();
+
+    router.forward(ForwardedMessage {
+        user_id: id,
+        address,
+        message: CurverMessageToReceive::Disconnect,
+    });
+}
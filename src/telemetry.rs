@@ -0,0 +1,30 @@
+use clap::ValueEnum;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogFormat {
+    /// Multi-line, human readable output
+    #[default]
+    Pretty,
+    /// One line per event
+    Compact,
+    /// One JSON object per line, for log aggregation
+    Json,
+}
+
+/// `RUST_LOG` takes precedence over `level` when it is set.
+pub fn init_tracing(level: &str, format: LogFormat) -> Result<(), String> {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(level))
+        .map_err(|error| format!("Invalid log level {}: {}", level, error))?;
+
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    let result = match format {
+        LogFormat::Pretty => builder.pretty().try_init(),
+        LogFormat::Compact => builder.compact().try_init(),
+        LogFormat::Json => builder.json().with_current_span(true).try_init(),
+    };
+
+    result.map_err(|error| format!("Could not install the logger: {}", error))
+}
@@ -0,0 +1,106 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc as std_mpsc, Arc,
+    },
+    thread,
+};
+
+use tokio::{runtime::Handle, task::JoinHandle};
+
+use crate::game::scheduler::GameScheduler;
+
+/// Rooms are spread over a fixed number of worker threads. Each worker runs a single threaded
+/// runtime, so a room and its game always stay on the same thread, and ticks all of its
+/// games in one wakeup.
+#[derive(Clone)]
+pub struct WorkerPool {
+    workers: Arc<Vec<Worker>>,
+}
+
+pub struct Worker {
+    runtime: Handle,
+    game_scheduler: GameScheduler,
+    room_count: Arc<AtomicUsize>,
+}
+
+impl WorkerPool {
+    pub fn new(worker_count: usize) -> WorkerPool {
+        let workers = (0..worker_count.max(1)).map(Worker::start).collect();
+
+        WorkerPool {
+            workers: Arc::new(workers),
+        }
+    }
+
+    /// The worker with the fewest rooms gets the next one.
+    pub fn least_loaded(&self) -> &Worker {
+        self.workers
+            .iter()
+            .min_by_key(|worker| worker.room_count.load(Ordering::Relaxed))
+            .expect("pool has at least one worker")
+    }
+}
+
+impl Worker {
+    fn start(index: usize) -> Worker {
+        let (setup_transmitter, setup_receiver) = std_mpsc::channel();
+
+        thread::Builder::new()
+            .name(format!("curver-worker-{}", index))
+            .spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("worker runtime builds");
+
+                runtime.block_on(async {
+                    let _ = setup_transmitter.send((Handle::current(), GameScheduler::start()));
+                    std::future::pending::<()>().await;
+                });
+            })
+            .expect("worker thread starts");
+
+        let (runtime, game_scheduler) = setup_receiver.recv().expect("worker thread starts");
+
+        Worker {
+            runtime,
+            game_scheduler,
+            room_count: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Games of rooms on this worker have to be scheduled here.
+    pub fn game_scheduler(&self) -> GameScheduler {
+        self.game_scheduler.clone()
+    }
+
+    /// The room counts towards the worker's load until it stops, also if it panics.
+    pub fn spawn_room<F>(&self, room: F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let room_count = RoomCount::increment(self.room_count.clone());
+
+        self.runtime.spawn(async move {
+            let _room_count = room_count;
+            room.await;
+        })
+    }
+}
+
+struct RoomCount(Arc<AtomicUsize>);
+
+impl RoomCount {
+    fn increment(count: Arc<AtomicUsize>) -> RoomCount {
+        count.fetch_add(1, Ordering::Relaxed);
+        RoomCount(count)
+    }
+}
+
+impl Drop for RoomCount {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,56 @@
+use std::{sync::Arc, time::Duration};
+
+use curver_backend::{
+    client_sink::{CurverAddress, InMemoryClientSink, Outgoing},
+    constants::CLIENT_OUTGOING_QUEUE_CAPACITY,
+    game::player::PlayerUuid,
+    message::{CurverMessageToReceive, CurverMessageToSend, ForwardedMessage},
+    routing::Router,
+};
+use tokio::{sync::mpsc::Receiver, time::timeout};
+
+pub const WAIT: Duration = Duration::from_secs(5);
+
+/// Talks to the server through the router, like a connection of any transport would.
+pub struct InMemoryClient {
+    pub user_id: PlayerUuid,
+    address: CurverAddress,
+    receiver: Receiver<Outgoing>,
+    router: Router,
+}
+
+impl InMemoryClient {
+    pub fn connect(router: Router) -> InMemoryClient {
+        let (sink, receiver) = InMemoryClientSink::new(CLIENT_OUTGOING_QUEUE_CAPACITY);
+        let client = InMemoryClient {
+            user_id: PlayerUuid::new(),
+            address: Arc::new(sink),
+            receiver,
+            router,
+        };
+
+        client.send(CurverMessageToReceive::Connect);
+        client
+    }
+
+    pub fn send(&self, message: CurverMessageToReceive) {
+        self.router.forward(ForwardedMessage {
+            message,
+            user_id: self.user_id,
+            address: self.address.clone(),
+        });
+    }
+
+    pub async fn receive_until<T>(&mut self, f: impl Fn(&CurverMessageToSend) -> Option<T>) -> T {
+        loop {
+            let outgoing = timeout(WAIT, self.receiver.recv())
+                .await
+                .expect("message arrives in time")
+                .expect("client is connected");
+
+            if let Some(found) = outgoing.messages().iter().find_map(&f) {
+                return found;
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use curver_backend::{
+    curver_error::JoinRoomError,
+    game::{GameOutcome, GameState},
+    http::spawn_server,
+    message::{CurverMessageToReceive, CurverMessageToSend},
+    room_access::RoomVisibility,
+    room_code::RoomIdentifier,
+};
+
+use common::InMemoryClient;
+
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn kicked_players_can_not_take_their_seat_back() {
+    let app_state = spawn_server(None, None, 1);
+
+    let mut host = InMemoryClient::connect(app_state.router());
+    host.send(CurverMessageToReceive::CreateRoom {
+        visibility: RoomVisibility::Public,
+        password: None,
+        max_players: None,
+    });
+    let room_id = host
+        .receive_until(|message| match message {
+            CurverMessageToSend::JoinedRoom { room_id, .. } => Some(*room_id),
+            _ => None,
+        })
+        .await;
+
+    let mut player = InMemoryClient::connect(app_state.router());
+    player.send(CurverMessageToReceive::JoinRoom {
+        room_id: RoomIdentifier::Uuid(room_id),
+        password: None,
+        invite_token: None,
+    });
+    let reconnect_token = player
+        .receive_until(|message| match message {
+            CurverMessageToSend::JoinedRoom {
+                reconnect_token, ..
+            } => Some(reconnect_token.clone()),
+            _ => None,
+        })
+        .await;
+
+    for client in [&host, &player] {
+        client.send(CurverMessageToReceive::IsReady { is_ready: true });
+    }
+    host.receive_until(|message| match message {
+        CurverMessageToSend::Update {
+            game_state: GameState::Started,
+            ..
+        } => Some(()),
+        _ => None,
+    })
+    .await;
+
+    host.send(CurverMessageToReceive::KickPlayer {
+        user_id: player.user_id,
+    });
+    player
+        .receive_until(|message| match message {
+            CurverMessageToSend::Kicked => Some(()),
+            _ => None,
+        })
+        .await;
+
+    player.send(CurverMessageToReceive::RejoinRoom {
+        room_id,
+        reconnect_token,
+    });
+    let code = player
+        .receive_until(|message| match message {
+            CurverMessageToSend::JoinRoomError { code, .. } => Some(code.clone()),
+            _ => None,
+        })
+        .await;
+    assert_eq!(code, JoinRoomError::SeatNotHeld);
+
+    // The kicked player is out right away instead of keeping a seat to come back to
+    let outcome = host
+        .receive_until(|message| match message {
+            CurverMessageToSend::GameEnded { outcome, .. } => Some(outcome.clone()),
+            _ => None,
+        })
+        .await;
+    assert_eq!(
+        outcome,
+        GameOutcome::Winner {
+            user_id: host.user_id
+        }
+    );
+}
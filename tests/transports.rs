@@ -0,0 +1,151 @@
+use std::{net::TcpListener as StdTcpListener, time::Duration};
+
+use curver_backend::{
+    client_sink::{ClientSink, InMemoryClientSink},
+    constants::TCP_MAX_LINE_LENGTH,
+    http::spawn_server,
+    message::{CurverMessageToReceive, CurverMessageToSend},
+    room_access::RoomVisibility,
+};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{tcp::OwnedReadHalf, TcpStream},
+    time::timeout,
+};
+
+use common::{InMemoryClient, WAIT};
+
+mod common;
+
+fn free_address() -> String {
+    let listener = StdTcpListener::bind("127.0.0.1:0").expect("port is free");
+    listener
+        .local_addr()
+        .expect("listener has an address")
+        .to_string()
+}
+
+/// Retries until the listener spawned by the server accepts connections.
+async fn connect(address: &str) -> TcpStream {
+    for _ in 0..50 {
+        if let Ok(stream) = TcpStream::connect(address).await {
+            return stream;
+        }
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    panic!("TCP listener did not start");
+}
+
+/// Batches are unpacked, so every line yields its messages one by one.
+async fn receive_until_type(lines: &mut BufReader<OwnedReadHalf>, message_type: &str) -> Value {
+    loop {
+        let mut line = String::new();
+        let read = timeout(WAIT, lines.read_line(&mut line))
+            .await
+            .expect("message arrives in time")
+            .expect("connection is readable");
+        assert!(read > 0, "connection closed");
+
+        let value: Value = serde_json::from_str(&line).expect("server sends JSON");
+        let messages = match value["type"].as_str() {
+            Some("batch") => value["messages"].as_array().cloned().unwrap_or_default(),
+            _ => vec![value],
+        };
+
+        if let Some(message) = messages
+            .into_iter()
+            .find(|message| message["type"] == message_type)
+        {
+            return message;
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tcp_and_in_memory_clients_share_a_room() {
+    let tcp_address = free_address();
+    let app_state = spawn_server(Some(tcp_address.clone()), None, 1);
+
+    let mut in_memory_client = InMemoryClient::connect(app_state.router());
+    in_memory_client.send(CurverMessageToReceive::CreateRoom {
+        visibility: RoomVisibility::Public,
+        password: None,
+        max_players: None,
+    });
+    let room_id = in_memory_client
+        .receive_until(|message| match message {
+            CurverMessageToSend::JoinedRoom { room_id, .. } => Some(*room_id),
+            _ => None,
+        })
+        .await;
+
+    let (reader, mut writer) = connect(&tcp_address).await.into_split();
+    let mut lines = BufReader::new(reader);
+    let join_room = json!({ "type": "joinRoom", "roomId": room_id.to_string() });
+    writer
+        .write_all(format!("{}\n", join_room).as_bytes())
+        .await
+        .unwrap();
+
+    let joined_room = receive_until_type(&mut lines, "joinedRoom").await;
+    assert_eq!(joined_room["roomId"], room_id.to_string());
+
+    let tcp_user_id = joined_room["userId"].as_str().unwrap().to_string();
+    in_memory_client
+        .receive_until(|message| match message {
+            CurverMessageToSend::Update { players, .. }
+                if players
+                    .iter()
+                    .any(|player| player.id.to_string() == tcp_user_id) =>
+            {
+                Some(())
+            }
+            _ => None,
+        })
+        .await;
+
+    // Chat goes through the room, so it reaches the other transport too
+    in_memory_client.send(CurverMessageToReceive::SendChat {
+        text: "hello".to_string(),
+    });
+    let chat_message = receive_until_type(&mut lines, "chatMessage").await;
+    assert_eq!(chat_message["text"], "hello");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tcp_clients_sending_overly_long_lines_are_disconnected() {
+    let tcp_address = free_address();
+    let _app_state = spawn_server(Some(tcp_address.clone()), None, 1);
+
+    let (reader, mut writer) = connect(&tcp_address).await.into_split();
+    let mut lines = BufReader::new(reader);
+
+    let long_line = "x".repeat(TCP_MAX_LINE_LENGTH + 1);
+    let _ = writer.write_all(long_line.as_bytes()).await;
+    let _ = writer.write_all(b"\n").await;
+
+    let mut line = String::new();
+    let read = timeout(WAIT, lines.read_line(&mut line))
+        .await
+        .expect("connection closes in time")
+        .unwrap_or(0);
+    assert_eq!(read, 0);
+}
+
+#[tokio::test]
+async fn in_memory_clients_that_fall_behind_are_lagging() {
+    let (sink, mut receiver) = InMemoryClientSink::new(1);
+    let lagging = sink.lagging();
+
+    sink.do_send(CurverMessageToSend::LeftRoom);
+    sink.do_send(CurverMessageToSend::LeftRoom);
+
+    timeout(WAIT, lagging.notified())
+        .await
+        .expect("client is lagging");
+    assert!(receiver.recv().await.is_some());
+    assert!(receiver.try_recv().is_err());
+}